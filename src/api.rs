@@ -0,0 +1,360 @@
+use crate::constants::{API_MAX_CONNECTIONS, API_MAX_REQUEST, API_POLL_INTERVAL, API_TIMEOUT};
+use crate::error::Error;
+
+use std::io::{self, BufRead, BufReader, Read, Take, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+/// What a script asks the app to do. Over the control socket this is written
+/// as e.g. `{"command": "start", "name": "Write docs", "tag": "work"}`.
+#[derive(Deserialize)]
+#[serde(tag = "command", rename_all = "lowercase")]
+pub enum ApiCommand {
+    Status,
+    Start {
+        name: String,
+        #[serde(default)]
+        tag: String,
+    },
+    Pause,
+    Resume,
+    Stop,
+    Entries,
+    Tags,
+}
+
+/// The app's answer to an `ApiCommand`.
+pub struct ApiReply {
+    /// An HTTP status code.
+    pub status: u16,
+    pub body: Value,
+}
+
+impl ApiReply {
+    pub fn ok(body: Value) -> Self {
+        Self { status: 200, body }
+    }
+
+    /// A reply with a body of `{"error": message}`.
+    pub fn error(status: u16, message: &str) -> Self {
+        Self {
+            status,
+            body: json!({ "error": message }),
+        }
+    }
+}
+
+/// A command along with where to send its reply.
+pub type ApiRequest = (ApiCommand, Sender<ApiReply>);
+
+/// The body of `POST /start`.
+#[derive(Deserialize)]
+pub struct StartBody {
+    pub name: String,
+    #[serde(default)]
+    pub tag: String,
+}
+
+/// An HTTP server on `127.0.0.1` that lets scripts and editor plugins control
+/// the timer. Requests are handed to the app through `requests`, so they act on
+/// the same state as the GUI. The server stops shortly after this is dropped.
+pub struct ApiServer {
+    pub port: u16,
+    pub token: String,
+    pub requests: Receiver<ApiRequest>,
+    /// The token the server checks, shared so it can change without a restart.
+    shared_token: Arc<Mutex<String>>,
+    stop: Arc<AtomicBool>,
+}
+
+impl ApiServer {
+    /// Starts listening on `port`. Only requests carrying `token` are passed on.
+    /// `ctx` is repainted for every request, so it's answered even while nothing
+    /// else is happening in the window.
+    pub fn start(port: u16, token: String, ctx: egui::Context) -> io::Result<Self> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))?;
+        listener.set_nonblocking(true)?;
+
+        let (sender, requests) = mpsc::channel();
+        let shared_token = Arc::new(Mutex::new(token.clone()));
+        let stop = Arc::new(AtomicBool::new(false));
+        {
+            let stop = stop.clone();
+            let shared_token = shared_token.clone();
+            let active = Arc::new(AtomicUsize::new(0));
+            thread::spawn(move || {
+                while !stop.load(Ordering::Relaxed) {
+                    match listener.accept() {
+                        // Every connection takes a thread, so only a few are
+                        // served at once and the rest are turned away.
+                        Ok((mut stream, _))
+                            if active.load(Ordering::Relaxed) >= API_MAX_CONNECTIONS =>
+                        {
+                            let reply = ApiReply::error(503, "Too many requests at once");
+                            let _ = write_reply(&mut stream, &reply);
+                        }
+                        // A slow client only holds up its own request, and a
+                        // broken connection only loses its own request.
+                        Ok((stream, _)) => {
+                            let token = shared_token.lock().unwrap().clone();
+                            let sender = sender.clone();
+                            let ctx = ctx.clone();
+                            let connection = ActiveConnection::new(&active);
+                            thread::spawn(move || {
+                                let _ = handle_connection(stream, &token, &sender, &ctx);
+                                drop(connection);
+                            });
+                        }
+                        Err(_) => thread::sleep(API_POLL_INTERVAL),
+                    }
+                }
+            });
+        }
+
+        Ok(Self {
+            port,
+            token,
+            requests,
+            shared_token,
+            stop,
+        })
+    }
+
+    /// Only lets requests carrying `token` through from now on.
+    pub fn set_token(&mut self, token: String) {
+        *self.shared_token.lock().unwrap() = token.clone();
+        self.token = token;
+    }
+}
+
+impl Drop for ApiServer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+
+        // The server might be waiting on a reply, which would never come.
+        for (_, reply) in self.requests.try_iter() {
+            let _ = reply.send(ApiReply::error(503, "The API was turned off"));
+        }
+    }
+}
+
+/// A random token for the API, made of 32 bytes from the operating system.
+pub fn new_token() -> Result<String, Error> {
+    let mut bytes = [0; 32];
+    getrandom::getrandom(&mut bytes).map_err(|e| Error::NoRandomness(e.to_string()))?;
+    Ok(bytes.iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+/// Counts a connection as being handled until it's dropped.
+struct ActiveConnection(Arc<AtomicUsize>);
+
+impl ActiveConnection {
+    fn new(active: &Arc<AtomicUsize>) -> Self {
+        active.fetch_add(1, Ordering::Relaxed);
+        Self(active.clone())
+    }
+}
+
+impl Drop for ActiveConnection {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+struct HttpRequest {
+    method: String,
+    path: String,
+    authorization: Option<String>,
+    content_length: u64,
+    body: Vec<u8>,
+}
+
+type HttpReader<'a> = BufReader<Take<&'a TcpStream>>;
+
+fn handle_connection(
+    mut stream: TcpStream,
+    token: &str,
+    sender: &Sender<ApiRequest>,
+    ctx: &egui::Context,
+) -> io::Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(API_TIMEOUT))?;
+
+    let mut reader = BufReader::new((&stream).take(API_MAX_REQUEST));
+    let reply = match read_head(&mut reader)
+        .and_then(|request| answer(request, &mut reader, token, sender, ctx))
+    {
+        Ok(reply) => reply,
+        Err(e) if e.kind() == io::ErrorKind::InvalidData => ApiReply::error(400, &e.to_string()),
+        Err(e) => return Err(e),
+    };
+
+    write_reply(&mut stream, &reply)
+}
+
+/// Reads the request line and the headers, but not the body.
+fn read_head(reader: &mut HttpReader) -> io::Result<HttpRequest> {
+    let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg);
+
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return Err(invalid("Malformed request line"));
+    };
+
+    let method = method.to_string();
+    let path = target.split('?').next().unwrap_or_default().to_string();
+
+    let mut content_length = 0;
+    let mut authorization = None;
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Err(invalid("The request ended early"));
+        }
+
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+
+        let Some((name, value)) = header.split_once(':') else {
+            return Err(invalid("Malformed header"));
+        };
+
+        match name.trim().to_lowercase().as_str() {
+            "content-length" => {
+                content_length = value
+                    .trim()
+                    .parse()
+                    .map_err(|_| invalid("Malformed Content-Length"))?;
+            }
+            "authorization" => authorization = Some(value.trim().to_string()),
+            _ => {}
+        }
+    }
+
+    Ok(HttpRequest {
+        method,
+        path,
+        authorization,
+        content_length,
+        body: Vec::new(),
+    })
+}
+
+/// Checks the token, reads the body and hands the request to the app. The body
+/// is only read once the token is known to be right.
+fn answer(
+    mut request: HttpRequest,
+    reader: &mut HttpReader,
+    token: &str,
+    sender: &Sender<ApiRequest>,
+    ctx: &egui::Context,
+) -> io::Result<ApiReply> {
+    let given = request
+        .authorization
+        .as_deref()
+        .and_then(|authorization| authorization.strip_prefix("Bearer "));
+    if !given.is_some_and(|given| same_token(given.trim(), token)) {
+        return Ok(ApiReply::error(401, "Missing or wrong token"));
+    }
+
+    if request.content_length > API_MAX_REQUEST {
+        return Ok(ApiReply::error(413, "The body is too large"));
+    }
+
+    // The headers used up part of the limit, the body gets a limit of its own.
+    reader.get_mut().set_limit(request.content_length);
+    request.body = vec![0; request.content_length as usize];
+    reader.read_exact(&mut request.body)?;
+
+    Ok(match parse_command(&request) {
+        Ok(command) => ask_app(command, sender, ctx),
+        Err(reply) => reply,
+    })
+}
+
+/// Hands `command` to the app and waits for its reply.
+pub fn ask_app(command: ApiCommand, sender: &Sender<ApiRequest>, ctx: &egui::Context) -> ApiReply {
+    let (reply_sender, reply) = mpsc::channel();
+    if sender.send((command, reply_sender)).is_err() {
+        return ApiReply::error(503, "actt is closing");
+    }
+
+    ctx.request_repaint();
+    reply
+        .recv_timeout(API_TIMEOUT)
+        .unwrap_or_else(|_| ApiReply::error(503, "actt didn't answer in time"))
+}
+
+fn parse_command(request: &HttpRequest) -> Result<ApiCommand, ApiReply> {
+    let command = match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/status") => ApiCommand::Status,
+        ("GET", "/entries") => ApiCommand::Entries,
+        ("GET", "/tags") => ApiCommand::Tags,
+        ("POST", "/start") => {
+            let body: StartBody = serde_json::from_slice(&request.body)
+                .map_err(|e| ApiReply::error(400, &format!("Malformed body: {}", e)))?;
+            ApiCommand::Start {
+                name: body.name,
+                tag: body.tag,
+            }
+        }
+        ("POST", "/pause") => ApiCommand::Pause,
+        ("POST", "/resume") => ApiCommand::Resume,
+        ("POST", "/stop") => ApiCommand::Stop,
+        (_, "/status" | "/entries" | "/tags" | "/start" | "/pause" | "/resume" | "/stop") => {
+            return Err(ApiReply::error(405, "Method not allowed"));
+        }
+        _ => return Err(ApiReply::error(404, "Not found")),
+    };
+
+    Ok(command)
+}
+
+fn write_reply(stream: &mut TcpStream, reply: &ApiReply) -> io::Result<()> {
+    let body = reply.body.to_string();
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        reply.status,
+        reason(reply.status),
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        409 => "Conflict",
+        413 => "Payload Too Large",
+        503 => "Service Unavailable",
+        _ => "",
+    }
+}
+
+/// Compares without stopping at the first difference, so the token can't be
+/// worked out from how long requests take.
+fn same_token(given: &str, token: &str) -> bool {
+    !token.is_empty()
+        && given.len() == token.len()
+        && given
+            .bytes()
+            .zip(token.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
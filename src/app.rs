@@ -1,15 +1,30 @@
+use crate::api::{ApiCommand, ApiReply, ApiRequest, ApiServer};
+use crate::billing::{BillingReport, ReportFormat};
+use crate::cli::Args;
+use crate::constants::{
+    APP_TITLE, EMPTY_TAG, GOAL_WARNING_THRESHOLD, PALETTE_RECENT_LIMIT, TAG_SEPARATOR,
+};
+use crate::error::{Error, Result};
+use crate::idle::IdleMonitor;
+use crate::palette::Command;
 use crate::screens::*;
-use crate::user::{Config, Entry};
+use crate::socket::{socket_path, SocketServer};
+use crate::user::{
+    Action, Config, Entry, Favorite, Goal, GoalKind, PomodoroPhase, Settings, TagAssignBehavior,
+    TagNode, Theme, Timesheet, TimesheetRow, DEFAULT_PROFILE,
+};
 
 use std::{
-    fs,
-    path::{Path, PathBuf},
+    fs, io,
+    path::PathBuf,
     time::{Duration, Instant},
 };
 
+use chrono::{DateTime, Datelike, Local, NaiveDate};
 use dirs::config_dir;
 use egui::{Color32, Context, Response, Ui};
 use egui_dropdown::DropDownBox;
+use serde_json::{json, Value};
 
 /// We derive Deserialize/Serialize so we can persist app state on shutdown.
 #[derive(serde::Deserialize, serde::Serialize)]
@@ -24,6 +39,74 @@ pub struct App {
     pub activity_name: String,
     /// The tag of the current activity.
     pub tag_name: String,
+    /// The other tags of the current activity. Only `tag_name` decides the color.
+    pub extra_tags: Vec<String>,
+    /// Notes on the current activity.
+    pub notes: String,
+    /// When the activity was stopped, while the user is asked whether an entry
+    /// shorter than a minute should be saved.
+    #[serde(skip)]
+    pub short_entry_end: Option<Instant>,
+    /// Whether the current activity shows up in billing reports.
+    pub billable: bool,
+    /// The first day of the week shown on the timesheet screen. `None` is the current week.
+    #[serde(skip)]
+    pub timesheet_week: Option<NaiveDate>,
+    /// The activity of the row being added to the timesheet.
+    #[serde(skip)]
+    pub timesheet_activity: String,
+    /// The tag of the row being added to the timesheet.
+    #[serde(skip)]
+    pub timesheet_tag: String,
+    /// Rows added to the timesheet that don't have any time yet, as
+    /// `(name, tag_index, color_index)`.
+    #[serde(skip)]
+    pub timesheet_rows: Vec<(String, usize, usize)>,
+    /// The first day of the billing report, as `YYYY-MM-DD`.
+    #[serde(skip)]
+    pub billing_from: String,
+    /// The last day of the billing report, as `YYYY-MM-DD`.
+    #[serde(skip)]
+    pub billing_to: String,
+    /// The tag being typed in before it is added to the other tags.
+    #[serde(skip)]
+    pub new_extra_tag: String,
+    /// The other tags of the entry being retagged in the history screen.
+    #[serde(skip)]
+    pub retag_extra_tags: Vec<String>,
+    /// What the history screen is filtered by.
+    #[serde(skip)]
+    pub history_search: String,
+    /// The entry whose notes are being edited in the history screen.
+    #[serde(skip)]
+    pub notes_entry: Option<usize>,
+    /// The notes being edited, saved into `notes_entry` once the user is done.
+    #[serde(skip)]
+    pub edit_notes: String,
+    /// The entry picked with the keyboard in the history screen.
+    #[serde(skip)]
+    pub history_row: Option<usize>,
+    /// Scrolls the history screen to `history_row` on the next frame.
+    #[serde(skip)]
+    pub scroll_to_row: bool,
+    /// Puts the cursor in the activity text edit on the next frame.
+    #[serde(skip)]
+    pub focus_activity: bool,
+    /// Whether the shortcut cheat sheet is shown.
+    #[serde(skip)]
+    pub show_shortcuts: bool,
+    /// The action waiting for a new shortcut to be pressed in the settings screen.
+    #[serde(skip)]
+    pub recording_shortcut: Option<Action>,
+    /// Whether the command palette is shown.
+    #[serde(skip)]
+    pub show_palette: bool,
+    /// What the command palette is searching for.
+    #[serde(skip)]
+    pub palette_query: String,
+    /// The position of the highlighted match in the command palette.
+    #[serde(skip)]
+    pub palette_selected: usize,
 
     /// Path to the config file.
     #[serde(skip)]
@@ -34,24 +117,115 @@ pub struct App {
     /// Keeps track of the user's configs.
     #[serde(skip)]
     pub config: Config,
+    /// Path to the settings file.
+    #[serde(skip)]
+    pub settings_file: PathBuf,
+    /// The user's preferences and profiles, kept apart from the tracking data.
+    #[serde(skip)]
+    pub settings: Settings,
+    /// Name of the profile being made from the menu bar.
+    #[serde(skip)]
+    pub new_profile: String,
+    /// Options given on the command line.
+    #[serde(skip)]
+    pub args: Args,
+    /// The folder the data files of every profile are kept in.
+    #[serde(skip)]
+    pub data_dir: PathBuf,
+    /// Why the data folder can't be used, shown on the error screen.
+    #[serde(skip)]
+    pub data_error: Option<Error>,
+    /// The data folder the user wants to use instead, as typed into a text edit.
+    #[serde(skip)]
+    pub new_data_dir: String,
 
-    /// UNUSED. TODO.
     /// A tally of how long the user paused an activity throughout the
-    /// entire run of the activity.
+    /// entire run of the activity. Only finished pauses are counted.
     #[serde(skip)]
     pub total_pause_time: Duration,
-    /// The amount of time the user is pause for each time, instead of throughout
-    /// the entire run of the activity.
+    /// When the current pause started. `None` if the activity isn't paused.
     #[serde(skip)]
     pub pause_time: Option<Instant>,
-    /// The amount of time passed when conducting the activity.
-    /// `total_time = pause_time + work_time`
+    /// The length of each finished pause, in the order they were taken.
+    #[serde(skip)]
+    pub pauses: Vec<Duration>,
+    /// When the activity was started.
+    /// `total_time.elapsed() = pause time + work_time`
     #[serde(skip)]
     pub total_time: Option<Instant>,
+    /// The date and time the activity was started, for placing it on a calendar.
+    #[serde(skip)]
+    pub started_at: Option<DateTime<Local>>,
     /// The amount of time the user is worked on an activity.
+    /// Kept up to date by `update_work_time`.
     #[serde(skip)]
     pub work_time: Duration,
 
+    // This group is for time-boxed activities.
+    /// How many minutes the user plans to spend on the next activity. `0` means no plan.
+    pub planned_minutes: u64,
+    /// Stop the activity automatically once the planned time is used up.
+    pub auto_stop: bool,
+    /// Tags whose children are hidden on the tags screen, normalized.
+    pub collapsed_tags: Vec<String>,
+    /// How long the current activity was planned to take, if it was time-boxed.
+    #[serde(skip)]
+    pub planned_time: Option<Duration>,
+
+    // This group is for pomodoro mode.
+    /// Whether the current activity is split into pomodoro work and break phases.
+    pub pomodoro: bool,
+    /// The pomodoro phase the user is currently in.
+    #[serde(skip)]
+    pub pomodoro_phase: PomodoroPhase,
+    /// `work_time` at the moment the current work phase started.
+    #[serde(skip)]
+    pub pomodoro_mark: Duration,
+    /// How many work phases were completed during the current activity.
+    #[serde(skip)]
+    pub pomodoro_cycles: u32,
+    /// The message to show when a pomodoro phase ends, until the user dismisses it.
+    #[serde(skip)]
+    pub pomodoro_alert: Option<String>,
+    /// The title the window was last given.
+    #[serde(skip)]
+    pub window_title: String,
+
+    // This group is for idle detection.
+    /// The last time the user did anything in the app.
+    #[serde(skip)]
+    pub last_input: Option<Instant>,
+    /// When the user went idle, if they are idle right now.
+    #[serde(skip)]
+    pub idle_since: Option<Instant>,
+    /// The idle time the user is asked about once they're back, as `(start, end)`.
+    #[serde(skip)]
+    pub idle_interval: Option<(Instant, Instant)>,
+    /// Name of the activity the idle time is re-assigned to.
+    #[serde(skip)]
+    pub idle_activity_name: String,
+    /// Tag of the activity the idle time is re-assigned to.
+    #[serde(skip)]
+    pub idle_tag_name: String,
+    #[serde(skip)]
+    pub idle_monitor: IdleMonitor,
+
+    // This group is for switching to another activity without stopping first.
+    /// Determines whether the dialogue to switch activities will appear on screen.
+    #[serde(skip)]
+    pub show_switch_win: bool,
+    /// Name of the activity to switch to.
+    #[serde(skip)]
+    pub next_activity_name: String,
+    /// Tag of the activity to switch to.
+    #[serde(skip)]
+    pub next_tag_name: String,
+
+    /// The index of the entry the current activity was resumed from. Its time
+    /// is added onto that entry instead of creating a new one.
+    #[serde(skip)]
+    pub resume_index: Option<usize>,
+
     // This group is for changing the name of an activity.
     /// The dialog box to be shown when the user when they want
     /// to change the name of an activity in the history screen.
@@ -84,16 +258,30 @@ pub struct App {
     /// The new tag the user creates.
     #[serde(skip)]
     pub new_tag: String,
+    /// The color the user picked for `new_tag`, if they picked one.
+    #[serde(skip)]
+    pub new_tag_color: Option<Color32>,
     /// The original tag to be changed
     #[serde(skip)]
     pub target_tag: String,
     /// The index of `target_tag`
     #[serde(skip)]
     pub target_tag_index: usize,
-    /// UNUSED.
-    /// Boolean to determine whether to show the color picker on screen.
+    /// Boolean to determine whether to show the color picker on screen. It pops up
+    /// when a new tag's color is too close to another tag's.
     #[serde(skip)]
     pub show_color_picker: bool,
+    /// The color chosen in the color picker.
+    #[serde(skip)]
+    pub picked_color: Color32,
+    /// The entry that gets the new tag once a color is picked. `None` when the
+    /// tag is for the activity being started.
+    #[serde(skip)]
+    pub picker_entry: Option<usize>,
+
+    /// The index of the tag whose goals are being edited in the tags screen.
+    #[serde(skip)]
+    pub goal_tag_index: Option<usize>,
 
     // Misc. Ungrouped fields that don't belong to a particular group.
     /// Keeps track of which screen the user is currently on.
@@ -102,9 +290,32 @@ pub struct App {
     /// The message to display when there is an error of some sort.
     #[serde(skip)]
     pub warning: Option<String>,
+    /// An error serious enough to be shown in a popup, until the user dismisses it.
+    #[serde(skip)]
+    pub error: Option<Error>,
+    /// The scripting API server, while it's turned on.
+    #[serde(skip)]
+    pub api: Option<ApiServer>,
+    /// The port and token the API last failed to start with, so it isn't tried
+    /// again every frame.
+    #[serde(skip)]
+    pub api_failed: Option<(u16, String)>,
+    /// The port being typed or dragged in the settings, until it's done.
+    #[serde(skip)]
+    pub api_port_draft: Option<u16>,
+    /// The control socket, while it's turned on.
+    #[serde(skip)]
+    pub socket: Option<SocketServer>,
+    /// Whether the control socket couldn't be made, so it isn't tried again
+    /// every frame.
+    #[serde(skip)]
+    pub socket_failed: bool,
 
     /// Color for tags
     pub color: Color32,
+    /// Whether the user picked `color` themselves on the start screen.
+    #[serde(skip)]
+    pub color_chosen: bool,
     /// Identifies which text box should be focused.
     #[serde(skip)]
     pub focus: bool,
@@ -112,17 +323,20 @@ pub struct App {
 
 impl App {
     /// Called once before the first frame.
-    pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
+    pub fn new(cc: &eframe::CreationContext<'_>, args: Args) -> Self {
         // This is also where you can customized the look at feel of egui using
         // `cc.egui_ctx.set_visuals` and `cc.egui_ctx.set_fonts`.
 
         // Load previous app state (if any).
         // Note that you must enable the `persistence` feature for this to work.
-        if let Some(storage) = cc.storage {
-            return eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default();
-        }
-
-        Self::default()
+        let mut app: Self = match cc.storage {
+            Some(storage) => eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default(),
+            None => Self::default(),
+        };
+
+        app.args = args;
+        app.open_data_dir();
+        app
     }
 }
 
@@ -134,50 +348,137 @@ impl eframe::App for App {
 
     /// Called each time the UI needs repainting, which may be many times per second.
     /// Put your widgets into a `SidePanel`, `TopPanel`, `CentralPanel`, `Window` or `Area`.
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+    fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        // A pomodoro alert goes in the title, so it shows in the taskbar while
+        // actt is in the background.
+        let title = match &self.pomodoro_alert {
+            Some(msg) => format!("{} - {}", msg, APP_TITLE),
+            None => APP_TITLE.to_string(),
+        };
+        if title != self.window_title {
+            frame.set_window_title(&title);
+            self.window_title = title;
+        }
+
+        let dark_mode = self.settings.preferences.theme == Theme::Dark;
+        if ctx.style().visuals.dark_mode != dark_mode {
+            ctx.set_visuals(if dark_mode {
+                egui::Visuals::dark()
+            } else {
+                egui::Visuals::light()
+            });
+        }
+
+        self.handle_shortcuts(ctx);
+        self.update_api(ctx);
+        self.update_socket(ctx);
+
         match self.screen {
-            Screen::Start => start_screen(self, ctx, _frame),
-            Screen::Tracking | Screen::Pause => tracking_screen(self, ctx, _frame),
-            Screen::History => history_screen(self, ctx, _frame),
-            Screen::Tags => tags_screen(self, ctx, _frame),
-            Screen::Settings => settings_screen(self, ctx, _frame),
+            Screen::Start => start_screen(self, ctx, frame),
+            Screen::Tracking | Screen::Pause => tracking_screen(self, ctx, frame),
+            Screen::History => history_screen(self, ctx, frame),
+            Screen::Tags => tags_screen(self, ctx, frame),
+            Screen::Billing => billing_screen(self, ctx, frame),
+            Screen::Timesheet => timesheet_screen(self, ctx, frame),
+            Screen::Settings => settings_screen(self, ctx, frame),
+            Screen::Error => error_screen(self, ctx, frame),
         }
-    }
-}
 
-impl Default for App {
-    fn default() -> Self {
-        // Gets the home directory (platform independent)
-        let home = config_dir().unwrap();
-        let home = format!("{}/actt", home.display());
+        if self.show_color_picker {
+            tag_color_picker_window(self, ctx);
+        }
+
+        if self.show_shortcuts {
+            shortcuts_window(self, ctx);
+        }
 
-        // Creates the `actt` folder in the home directory if it doesn't exist.
-        let home = Path::new(&home).to_owned();
-        if !Path::try_exists(&home).unwrap() {
-            fs::create_dir(&home).unwrap();
+        if self.show_palette {
+            command_palette(self, ctx);
         }
 
-        // Creates a config file where data is stored.
-        let config_file = format!("{}/actt.json", home.display());
-        let config_file = Path::new(&config_file);
-        if !Path::try_exists(config_file).unwrap() {
-            fs::File::create(config_file).unwrap();
+        if self.error.is_some() {
+            error_popup(self, ctx);
         }
+    }
+}
 
+impl Default for App {
+    fn default() -> Self {
         Self {
             tmp: "".to_string(),
             activity_name: "".to_string(),
             tag_name: "".to_string(),
-
-            config_file: config_file.to_path_buf(),
+            extra_tags: Vec::new(),
+            new_extra_tag: String::new(),
+            notes: String::new(),
+            short_entry_end: None,
+            billable: true,
+            timesheet_week: None,
+            timesheet_activity: String::new(),
+            timesheet_tag: String::new(),
+            timesheet_rows: Vec::new(),
+            billing_from: Local::now()
+                .date_naive()
+                .with_day(1)
+                .unwrap_or_default()
+                .to_string(),
+            billing_to: Local::now().date_naive().to_string(),
+            history_search: String::new(),
+            notes_entry: None,
+            edit_notes: String::new(),
+            history_row: None,
+            scroll_to_row: false,
+            focus_activity: false,
+            show_shortcuts: false,
+            recording_shortcut: None,
+            show_palette: false,
+            palette_query: String::new(),
+            palette_selected: 0,
+            retag_extra_tags: Vec::new(),
+
+            config_file: PathBuf::new(),
             config_file_updated: true,
             config: Config::default(),
+            settings_file: PathBuf::new(),
+            settings: Settings::default(),
+            new_profile: "".to_string(),
+            args: Args::default(),
+            data_dir: PathBuf::new(),
+            data_error: None,
+            new_data_dir: "".to_string(),
 
             total_pause_time: Duration::from_secs(0),
             pause_time: None,
+            pauses: Vec::new(),
             total_time: None,
+            started_at: None,
             work_time: Duration::from_secs(0),
 
+            planned_minutes: 0,
+            auto_stop: false,
+            collapsed_tags: Vec::new(),
+            planned_time: None,
+
+            pomodoro: false,
+            pomodoro_phase: PomodoroPhase::Work,
+            pomodoro_mark: Duration::default(),
+            pomodoro_cycles: 0,
+            pomodoro_alert: None,
+            window_title: APP_TITLE.to_string(),
+
+            last_input: None,
+            idle_since: None,
+            idle_interval: None,
+            idle_activity_name: "".to_string(),
+            idle_tag_name: "".to_string(),
+            idle_monitor: IdleMonitor::default(),
+
+            show_switch_win: false,
+            next_activity_name: "".to_string(),
+            next_tag_name: "".to_string(),
+
+            resume_index: None,
+
             show_name_assign_dialog: false,
             new_name: "".to_string(),
             target_name_index: usize::MAX,
@@ -191,12 +492,24 @@ impl Default for App {
             target_tag: "".to_string(),
             target_tag_index: 0,
             new_tag: "".to_string(),
+            new_tag_color: None,
             show_color_picker: false,
+            picked_color: Color32::default(),
+            picker_entry: None,
+
+            goal_tag_index: None,
 
             screen: Screen::Start,
             warning: None,
+            error: None,
+            api: None,
+            api_failed: None,
+            api_port_draft: None,
+            socket: None,
+            socket_failed: false,
 
             color: Color32::BLACK,
+            color_chosen: false,
             focus: false,
         }
     }
@@ -215,34 +528,310 @@ impl App {
 
     /// Creates a combo box of tag list
     pub fn tag_list_combo_box(&mut self, ui: &mut Ui) -> Response {
+        let tag_list = self.tag_list();
+        Self::tag_combo_box(ui, &tag_list, "tags", &mut self.tag_name)
+    }
+
+    /// Creates a text edit for the activity name that suggests the names of
+    /// past activities.
+    pub fn activity_combo_box(&mut self, ui: &mut Ui) -> Response {
         ui.add(DropDownBox::from_iter(
-            &self.tag_list(),
-            "tags",
-            &mut self.tag_name,
-            |ui, text| {
-                let r = ui.selectable_label(false, text);
-                r
-            },
+            self.config.activity_names(),
+            "activities",
+            &mut self.activity_name,
+            |ui, text| ui.selectable_label(false, text),
         ))
     }
 
+    /// Creates a combo box of `tag_list` that writes the chosen tag into `tag_name`.
+    /// The tags are shown as a tree, with sub-tags indented below their parent.
+    /// `id` has to be unique for every combo box shown at the same time.
+    pub fn tag_combo_box(
+        ui: &mut Ui,
+        tag_list: &[String],
+        id: &str,
+        tag_name: &mut String,
+    ) -> Response {
+        // Only tags that exist are offered, written the way they are stored.
+        let tags = TagNode::tree(tag_list)
+            .into_iter()
+            .filter_map(|node| tag_list.get(node.tag_index?).cloned());
+        ui.add(DropDownBox::from_iter(tags, id, tag_name, |ui, text| {
+            let parts = TagNode::split(text);
+            let indent = "    ".repeat(parts.len().saturating_sub(1));
+            let name = parts.last().copied().unwrap_or_default();
+            ui.selectable_label(false, format!("{}{}", indent, name))
+        }))
+    }
+
+    /// Shows or hides the children of `path` on the tags screen.
+    pub fn toggle_collapsed(&mut self, path: &str) {
+        let path = Config::normalize_tag(path);
+        match self.collapsed_tags.iter().position(|tag| *tag == path) {
+            Some(index) => {
+                self.collapsed_tags.remove(index);
+            }
+            None => self.collapsed_tags.push(path),
+        }
+    }
+
+    /// Whether `path` is hidden because one of its parents is collapsed.
+    pub fn is_tag_hidden(&self, path: &str) -> bool {
+        let parts = TagNode::split(path);
+        (1..parts.len()).any(|depth| {
+            let parent = Config::normalize_tag(&parts[..depth].join(&TAG_SEPARATOR.to_string()));
+            self.collapsed_tags.contains(&parent)
+        })
+    }
+
+    /// Whether the children of `path` are hidden.
+    pub fn is_collapsed(&self, path: &str) -> bool {
+        self.collapsed_tags.contains(&Config::normalize_tag(path))
+    }
+
     pub fn write_config_file(&mut self) {
-        let json = serde_json::to_string(&self.config).unwrap();
-        fs::write(&self.config_file, json).unwrap();
-        self.config_file_updated = true;
+        match self.try_write_config_file() {
+            Ok(()) => self.config_file_updated = true,
+            Err(e) => {
+                self.config_file_updated = false;
+                self.show_error(e);
+            }
+        }
+    }
+
+    fn try_write_config_file(&self) -> Result<()> {
+        let json =
+            serde_json::to_string(&self.config).map_err(|e| Error::Serialize(e.to_string()))?;
+        fs::write(&self.config_file, json).map_err(|e| Error::io(&self.config_file, e))
+    }
+
+    /// Shows `error` to the user, in a popup if it's serious and in the warning
+    /// banner otherwise.
+    pub fn show_error(&mut self, error: Error) {
+        if error.is_serious() {
+            self.error = Some(error);
+        } else {
+            self.warning = Some(error.to_string());
+        }
+    }
+
+    /// Copies the config file into the `backups` folder next to it, keeping only
+    /// as many backups as the user asked for.
+    pub fn backup_config_file(&self) {
+        let retention = self.settings.preferences.backup_retention;
+        let (Some(data_dir), Some(stem)) =
+            (self.config_file.parent(), self.config_file.file_stem())
+        else {
+            return;
+        };
+
+        let backup_dir = data_dir.join("backups");
+        if retention == 0 || fs::create_dir_all(&backup_dir).is_err() {
+            return;
+        }
+
+        let prefix = format!("{}-", stem.to_string_lossy());
+        let timestamp = Local::now().format("%Y%m%d-%H%M%S");
+        let backup = backup_dir.join(format!("{}{}.json", prefix, timestamp));
+        if fs::copy(&self.config_file, backup).is_err() {
+            return;
+        }
+
+        // The timestamp in the name means sorting by name sorts by age.
+        let mut backups: Vec<PathBuf> = match fs::read_dir(&backup_dir) {
+            Ok(dir) => dir
+                .filter_map(|e| e.ok())
+                .filter(|e| e.file_name().to_string_lossy().starts_with(&prefix))
+                .map(|e| e.path())
+                .collect(),
+            Err(_) => return,
+        };
+        backups.sort();
+
+        let excess = backups.len().saturating_sub(retention);
+        for old_backup in &backups[..excess] {
+            let _ = fs::remove_file(old_backup);
+        }
+    }
+
+    pub fn write_settings_file(&mut self) -> Result<()> {
+        let toml = toml::to_string(&self.settings).map_err(|e| Error::Serialize(e.to_string()))?;
+        fs::write(&self.settings_file, toml).map_err(|e| Error::io(&self.settings_file, e))
+    }
+
+    /// Fails if the settings file can't be read as settings, so it isn't
+    /// overwritten with the defaults.
+    pub fn read_settings_file(&self) -> Result<Settings> {
+        match fs::read_to_string(&self.settings_file) {
+            Ok(contents) => toml::from_str(&contents).map_err(|e| Error::Parse {
+                path: self.settings_file.clone(),
+                reason: e.to_string(),
+            }),
+            // Preferences used to be stored in the config file, carry them over.
+            Err(_) if self.config_file.exists() => Ok(Settings {
+                preferences: self.try_read_config_file()?.preferences,
+                ..Settings::default()
+            }),
+            Err(_) => Ok(Settings::default()),
+        }
+    }
+
+    /// Finds where settings and tracking data are kept and loads them. If they
+    /// can't be used the error screen is shown instead.
+    pub fn open_data_dir(&mut self) {
+        match self.try_open_data_dir() {
+            Ok(()) => {
+                self.data_error = None;
+                if self.screen == Screen::Error {
+                    self.screen = Screen::Start;
+                }
+            }
+            Err(e) => {
+                self.data_error = Some(e);
+                self.screen = Screen::Error;
+            }
+        }
+    }
+
+    fn try_open_data_dir(&mut self) -> Result<()> {
+        let settings_dir = if self.args.is_portable() {
+            Args::portable_dir().ok_or(Error::NoExeDir)?
+        } else {
+            config_dir().ok_or(Error::NoConfigDir)?.join("actt")
+        };
+        fs::create_dir_all(&settings_dir).map_err(|e| Error::io(&settings_dir, e))?;
+
+        // Before profiles existed the data file was always next to the settings.
+        self.settings_file = settings_dir.join("settings.toml");
+        self.config_file = Settings::profile_file(&settings_dir, DEFAULT_PROFILE);
+        self.settings = self.read_settings_file()?;
+
+        self.data_dir = match self.args.data_dir_override() {
+            Some((data_dir, _)) => data_dir,
+            None => self.settings.data_dir.clone().unwrap_or(settings_dir),
+        };
+        self.use_profile(&self.settings.profile.clone())?;
+
+        // Written straight away so preferences carried over from the config file
+        // aren't lost once the config file is saved without them.
+        self.write_settings_file()?;
+        self.backup_config_file();
+        Ok(())
+    }
+
+    /// Keeps tracking data in `data_dir` from now on, or in the default folder if
+    /// it is `None`. Data that is already in the old folder isn't moved.
+    pub fn change_data_dir(&mut self, data_dir: Option<PathBuf>) {
+        self.settings.data_dir = data_dir;
+        if let Err(e) = self.write_settings_file() {
+            self.data_error = Some(e);
+            self.screen = Screen::Error;
+            return;
+        }
+
+        self.open_data_dir();
+    }
+
+    /// Switches to the data file of `profile`, creating it if it doesn't exist.
+    pub fn use_profile(&mut self, profile: &str) -> Result<()> {
+        let config_file = Settings::profile_file(&self.data_dir, profile);
+
+        if let Some(dir) = config_file.parent() {
+            fs::create_dir_all(dir).map_err(|e| Error::io(dir, e))?;
+        }
+
+        match fs::metadata(&config_file) {
+            Ok(metadata) if metadata.permissions().readonly() => {
+                return Err(Error::ReadOnly(config_file));
+            }
+            Ok(_) => (),
+            Err(_) => {
+                fs::File::create(&config_file).map_err(|e| Error::io(&config_file, e))?;
+            }
+        }
+
+        let previous_file = std::mem::replace(&mut self.config_file, config_file);
+        match self.try_read_config_file() {
+            Ok(config) => self.config = config,
+            Err(e) => {
+                self.config_file = previous_file;
+                return Err(e);
+            }
+        }
+
+        self.settings.profile = profile.to_string();
+        if !self.settings.profiles.iter().any(|e| e == profile) {
+            self.settings.profiles.push(profile.to_string());
+        }
+
+        Ok(())
+    }
+
+    /// Switches to another profile from the menu bar.
+    pub fn switch_profile(&mut self, profile: &str) {
+        match self
+            .use_profile(profile)
+            .and_then(|_| self.write_settings_file())
+        {
+            Ok(()) => self.warning = None,
+            Err(e) => self.show_error(e),
+        }
+    }
+
+    /// Makes a new profile named `new_profile` and switches to it.
+    pub fn create_profile(&mut self) {
+        let profile = self.new_profile.trim().to_string();
+        if !Settings::is_valid_profile_name(&profile) {
+            self.show_error(Error::InvalidProfileName(profile));
+            return;
+        }
+
+        if self.settings.profiles.contains(&profile) {
+            self.show_error(Error::ProfileExists(profile));
+            return;
+        }
+
+        self.new_profile.clear();
+        self.switch_profile(&profile);
+    }
+
+    /// Formats `duration` the way the user prefers.
+    pub fn format_time(&self, duration: Duration) -> String {
+        self.settings.preferences.time_format.format(duration)
     }
 
+    /// Reads the config file. If it can't be read, what is already loaded is used
+    /// instead so the file never gets overwritten with an empty config.
     pub fn read_config_file(&self) -> Config {
-        let file = fs::read(&self.config_file).unwrap();
-        let contents = std::str::from_utf8(&file[..]).unwrap();
-        match serde_json::from_str(contents) {
-            Ok(act) => act,
-            Err(_) => Config::default(),
+        self.try_read_config_file()
+            .unwrap_or_else(|_| self.config.clone())
+    }
+
+    pub fn try_read_config_file(&self) -> Result<Config> {
+        let contents =
+            fs::read_to_string(&self.config_file).map_err(|e| Error::io(&self.config_file, e))?;
+
+        // A config file that was just made is empty.
+        if contents.trim().is_empty() {
+            return Ok(Config::default());
         }
+
+        serde_json::from_str(&contents).map_err(|e| Error::Parse {
+            path: self.config_file.clone(),
+            reason: e.to_string(),
+        })
     }
 
     /// Assign a new name to an activity
-    pub fn assign_name(&mut self, ui: &mut egui::Ui, name: &String, index: usize) {
+    ///
+    /// # Return
+    /// Returns the name button, or `None` while the name is being edited.
+    pub fn assign_name(
+        &mut self,
+        ui: &mut egui::Ui,
+        name: &String,
+        index: usize,
+    ) -> Option<Response> {
         let same_index = self.target_name_index != usize::MAX && index == self.target_name_index;
 
         if self.show_name_assign_dialog && same_index {
@@ -259,8 +848,14 @@ impl App {
             // key then apply the changes.
             if lost_focus && key_pressed(egui::Key::Enter) {
                 if !self.new_name.trim().is_empty() {
-                    self.config.entry[index].name = self.new_name.clone();
-                    self.write_config_file();
+                    self.config = self.read_config_file();
+                    match self.check_entry_open(&self.config, index) {
+                        Ok(()) => {
+                            self.config.entry[index].name = self.new_name.clone();
+                            self.write_config_file();
+                        }
+                        Err(e) => self.show_error(e),
+                    }
                 }
                 self.show_name_assign_dialog = false;
                 self.focus = false;
@@ -272,50 +867,121 @@ impl App {
             }
         } else {
             let btn = egui::Button::new(name).frame(false);
-            if ui.add(btn).clicked() {
+            let r = ui.add(btn);
+            if r.clicked() {
                 self.target_name_index = index;
                 self.show_name_assign_dialog = true;
             };
+
+            return Some(r);
         }
+
+        None
     }
 
-    pub fn create_tag(&mut self, index: usize, list_of_colors: &[Color32]) {
-        let mut config_file = self.read_config_file();
-        if config_file.tag_list.contains(&self.new_tag) {
-            // FIXME:
-            // This is wrong. A user can rename a tag to be the same one, because they
-            // could have clicked on the wrong tag. Instead find the same color and return
-            // early.
-            todo!("Warn user that tag exists, choose another one. Create some update in the UI.");
+    /// Gives the entry at `index` the tag `new_tag`. If the tag already exists,
+    /// ignoring case and extra whitespace, it is reused along with its color.
+    /// Otherwise it is made with `color`, or a random color if that is `None` or
+    /// already taken by another tag.
+    pub fn create_tag(&mut self, index: usize, color: Option<Color32>) {
+        let new_tag = self.new_tag.trim().to_string();
+        // The user wants to decide the tag later.
+        if new_tag.is_empty() {
+            return;
         }
 
-        config_file.tag_list.push(self.new_tag.clone());
-        self.color = self.config.random_color(&list_of_colors, &self.color, None);
-        config_file.colors.push(self.color.clone());
+        let mut config_file = self.read_config_file();
+        if let Err(e) = self.check_entry_open(&config_file, index) {
+            self.show_error(e);
+            return;
+        }
 
-        if let Some(entry) = config_file.entry.get_mut(index) {
-            entry.tag_index = config_file.tag_list.len() - 1;
-            entry.color_index = config_file.colors.len() - 1;
+        let existing_tag_index = config_file.find_tag(&config_file.tag_list, &new_tag);
+        let wanted_color = color.unwrap_or(self.color);
+
+        if existing_tag_index.is_none()
+            && self.settings.preferences.tag_assign_behavior == TagAssignBehavior::Picker
+            && !config_file.is_usable_color(
+                &config_file.colors,
+                &wanted_color,
+                self.settings.preferences.theme.background(),
+            )
+        {
+            self.open_color_picker(Some(index), wanted_color);
+            return;
         }
+        let preferences = &self.settings.preferences;
+        let (tag_index, color_index) = config_file.ensure_tag(
+            &new_tag,
+            &wanted_color,
+            preferences.tag_palette,
+            preferences.theme.background(),
+        );
+        let tag_color = config_file.colors[color_index];
+
+        self.warning = color.and_then(|color| {
+            Self::tag_color_warning(
+                &config_file.tag_list[tag_index],
+                existing_tag_index.is_some(),
+                color,
+                tag_color,
+            )
+        });
+
+        let entry = &mut config_file.entry[index];
+        entry.tag_index = tag_index;
+        entry.color_index = color_index;
+        entry.extra_tags.retain(|extra| *extra != tag_index);
 
         self.config = config_file;
         self.write_config_file();
 
         if self.config_file_updated {
+            self.new_tag.clear();
+            self.new_tag_color = None;
             self.show_create_tag_win = false;
+            self.show_change_tag_win = false;
+        }
+    }
+
+    /// Explains why `tag` didn't get the color `wanted` the user picked for it,
+    /// and got `color` instead. `existed` is whether the tag was already there.
+    pub fn tag_color_warning(
+        tag: &str,
+        existed: bool,
+        wanted: Color32,
+        color: Color32,
+    ) -> Option<String> {
+        if wanted == color {
+            None
+        } else if existed {
+            Some(format!(
+                "\"{}\" already has its own color, it was kept.",
+                tag.trim()
+            ))
+        } else {
+            Some(format!(
+                "That color is already used by another tag, \"{}\" got a different one.",
+                tag.trim()
+            ))
         }
     }
 
+    /// Shows the color picker for a new tag, starting at `color`. The tag goes to
+    /// the entry at `entry`, or to the activity being started if it is `None`.
+    pub fn open_color_picker(&mut self, entry: Option<usize>, color: Color32) {
+        self.picker_entry = entry;
+        self.picked_color = color;
+        self.show_color_picker = true;
+        self.show_create_tag_win = false;
+        self.show_change_tag_win = false;
+    }
+
     /// Changes the existing tag to a current one.
     pub fn change_tag(&mut self, ctx: &Context, index: usize, list_of_colors: &[Color32]) {}
 
     /// The user can change or assign new tags based on the the cirumstance.
-    pub fn change_or_assign_tag(
-        &mut self,
-        ctx: &Context,
-        index: usize,
-        list_of_colors: &[Color32],
-    ) {
+    pub fn change_or_assign_tag(&mut self, ctx: &Context, index: usize) {
         egui::Window::new("").title_bar(false).show(ctx, |ui| {
             ui.label(
                 "You can create new tags as well, just type the name of a tag that doesn't exist.",
@@ -326,46 +992,11 @@ impl App {
                 ui.text_edit_singleline(&mut self.new_tag);
             });
 
-            // TODO: Add a color picker.
-            // If the preference is "picker" then a window will pop up with a color wheel that
-            // allows them to select a new color for the tag.
-            // Since this doesn't work yet, it always defaults to a random color.
-            self.color = self.config.random_color(&list_of_colors, &self.color, None);
-
+            // If the preference is "picker" and the color of a new tag clashes, a window
+            // will pop up with a color wheel that allows them to select a new color.
             ui.vertical_centered(|ui| {
-                let done_btn = ui.button("Done");
-                if !done_btn.clicked() {
-                    return;
-                }
-
-                // The user wants to decide the tag later.
-                if self.new_tag.is_empty() {
-                    return;
-                }
-
-                let mut config_file = self.read_config_file();
-                if config_file.tag_list.contains(&self.new_tag) {
-                    // FIXME:
-                    // This is wrong. A user can rename a tag to be the same one, because they
-                    // could have clicked on the wrong tag. Instead find the same color and return
-                    // early.
-                    todo!("Warn user that tag exists, choose another one.");
-                }
-
-                config_file.tag_list.push(self.new_tag.clone());
-                self.color = self.config.random_color(&list_of_colors, &self.color, None);
-                config_file.colors.push(self.color.clone());
-
-                if let Some(entry) = config_file.entry.get_mut(index) {
-                    entry.tag_index = config_file.tag_list.len() - 1;
-                    entry.color_index = config_file.colors.len() - 1;
-                }
-
-                self.config = config_file;
-                self.write_config_file();
-
-                if self.config_file_updated {
-                    self.show_create_tag_win = false;
+                if ui.button("Done").clicked() {
+                    self.create_tag(index, None);
                 }
             });
         });
@@ -376,93 +1007,1328 @@ impl App {
             .config
             .tag_list
             .iter()
-            .position(|tag| *tag == tag_to_delete)
-            .expect("Tag not found, which *should* be impossible.");
+            .position(|tag| *tag == tag_to_delete);
+
+        let Some(del_index) = del_index else {
+            self.show_error(Error::TagNotFound(tag_to_delete));
+            return;
+        };
 
         // Each tag has a color associated with it, if tag is deleted the
         // colors must be deleted along with it as well.
         self.config.tag_list.remove(del_index);
-        self.config.colors.remove(del_index);
+        let mut untagged = Vec::new();
+        for (index, entry) in self.config.entry.iter_mut().enumerate() {
+            if entry.remove_tag(del_index) {
+                untagged.push(index);
+            }
+        }
+        if del_index < self.config.colors.len() {
+            self.config.colors.remove(del_index);
+        }
+        if del_index < self.config.tag_goals.len() {
+            self.config.tag_goals.remove(del_index);
+        }
+        if del_index < self.config.tag_rates.len() {
+            self.config.tag_rates.remove(del_index);
+        }
+
+        // Entries that had the tag as their main one are left without a tag.
+        if !untagged.is_empty() {
+            let color = self.tag_color(EMPTY_TAG);
+            let preferences = &self.settings.preferences;
+            let (tag_index, color_index) = self.config.ensure_tag(
+                EMPTY_TAG,
+                &color,
+                preferences.tag_palette,
+                preferences.theme.background(),
+            );
+            for index in untagged {
+                let entry = &mut self.config.entry[index];
+                let extra_tags = std::mem::take(&mut entry.extra_tags);
+                entry.tag_index = tag_index;
+                entry.color_index = color_index;
+                entry.set_extra_tags(extra_tags);
+            }
+        }
+        self.goal_tag_index = None;
         self.write_config_file();
     }
 
-    /// Adds the details of an activity to `Config`.
-    pub fn add_entry(&mut self) {
-        // Logic for adding entries to the config file.
-        self.screen = Screen::History;
-        match self.pause_time {
-            Some(pause_time) => {
-                // Because `total_time` is an Instant adding it with a Duration makes
-                // it so that the Instant began by Duration. Explanation by Dr Nefario:
-                // let's say you have an instant for the time of 6AM, and it's currently 7AM.
-                // the elapsed time will be 1 hour.
-                // but if you add a 5 minute duration to the instant, making it 6:05AM, the elapsed time will now be 55 minutes
-                self.total_time = Some(self.total_time.unwrap() + pause_time.elapsed());
+    /// Sets whether the entry at `index` shows up in billing reports.
+    pub fn set_billable(&mut self, index: usize, billable: bool) {
+        self.config = self.read_config_file();
+        match self.check_entry_open(&self.config, index) {
+            Ok(()) => {
+                self.config.entry[index].billable = billable;
+                self.write_config_file();
             }
-            _ => (),
+            Err(e) => self.show_error(e),
         }
+    }
 
-        // TODO: Find a way to make checks for if preferences were changed
-        let mut config = self.read_config_file();
-        config.total_time.push(self.total_time.unwrap().elapsed());
-
-        if self.does_tag_exist(&config.tag_list, &self.tag_name) {
-            let existing_tag_index = self.config.find_tag(&self.tag_list(), &self.tag_name);
-            let mut color_index = self.config.find_color(&config.colors, &self.color);
+    /// The first day of the week shown on the timesheet screen.
+    pub fn timesheet_week(&self) -> NaiveDate {
+        self.timesheet_week.unwrap_or_else(|| {
+            Timesheet::week_of(
+                Local::now().date_naive(),
+                self.settings.preferences.week_start,
+            )
+        })
+    }
 
-            if color_index.is_none() {
-                // Creates a new color
-                config.colors.push(self.color.clone());
-                color_index = Some(config.colors.len() - 1);
-            }
+    /// Changes the time of a timesheet row on `day`, unless its week was submitted.
+    pub fn set_timesheet_time(&mut self, row: &TimesheetRow, day: NaiveDate, time: Duration) {
+        self.config = self.read_config_file();
+        let (day_start, _) = Timesheet::day_bounds(day);
+        if let Err(e) = self.check_week_open(&self.config, day_start) {
+            self.show_error(e);
+            return;
+        }
 
-            let new_entry = Entry::new(
-                self.activity_name.clone(),
-                existing_tag_index.unwrap(),
-                color_index.unwrap(),
-            );
-            config.entry.push(new_entry);
-            config.total_time.push(self.total_time.unwrap().elapsed());
-        } else {
-            // If true means a color already exists. There can't be clashing colors for
-            // tags. Therefore a random one will be assigned.
-            if self.config.tag_assign_behavior() == "random" {
-                if self
-                    .config
-                    .find_color(&config.colors, &self.color)
-                    .is_some()
-                {
-                    self.color = self.config.random_color(&config.colors, &self.color, None);
-                }
-            }
+        let week_start = Timesheet::week_of(day, self.settings.preferences.week_start);
 
-            config.colors.push(self.color.clone());
-            let new_color_index = config.colors.len() - 1;
-            config.total_time.push(self.total_time.unwrap().elapsed());
+        self.config.set_time_between(
+            &row.name,
+            row.tag_index,
+            row.color_index,
+            Timesheet::day_bounds(day),
+            Timesheet::week_bounds(week_start),
+            time,
+        );
+        self.write_config_file();
+    }
 
-            let new_tag_index = config.tag_list.len();
-            let entry = Entry::new(self.activity_name.clone(), new_tag_index, new_color_index);
-            config.entry.push(entry);
+    /// Adds a row for `timesheet_activity` under `timesheet_tag` to the timesheet.
+    pub fn add_timesheet_row(&mut self) {
+        let name = self.timesheet_activity.trim().to_string();
+        if name.is_empty() {
+            self.show_error(Error::EmptyActivityName);
+            return;
+        }
 
-            config.tag_list.push(self.tag_name.clone());
+        let mut tag = std::mem::take(&mut self.timesheet_tag);
+        if tag.trim().is_empty() {
+            tag = EMPTY_TAG.to_string();
         }
 
-        // config.set_tag_assign_behavior(self.tag_assign_behavior.clone());
+        self.config = self.read_config_file();
+        let color = self.tag_color(&tag);
+        let preferences = &self.settings.preferences;
+        let (tag_index, color_index) = self.config.ensure_tag(
+            &tag,
+            &color,
+            preferences.tag_palette,
+            preferences.theme.background(),
+        );
+        self.write_config_file();
 
-        self.config = config;
+        self.timesheet_rows.push((name, tag_index, color_index));
+        self.timesheet_activity.clear();
+    }
+
+    /// Locks or unlocks the week shown on the timesheet screen.
+    pub fn set_week_submitted(&mut self, submitted: bool) {
+        self.config = self.read_config_file();
+        let week_start = self.timesheet_week();
+        self.config.set_week_submitted(week_start, submitted);
         self.write_config_file();
+    }
 
-        self.pause_time = None;
-        self.total_pause_time = Duration::default();
-        self.work_time = Duration::default();
+    /// The dates typed into the billing screen, if they can be read.
+    pub fn billing_dates(&self) -> Option<(NaiveDate, NaiveDate)> {
+        let from = NaiveDate::parse_from_str(self.billing_from.trim(), "%Y-%m-%d").ok()?;
+        let to = NaiveDate::parse_from_str(self.billing_to.trim(), "%Y-%m-%d").ok()?;
+        Some((from, to))
     }
 
-    // TODO: This should be inside the config utilities module
-    pub fn does_tag_exist(&self, tag_list: &[String], cur_tag: &String) -> bool {
-        if tag_list.contains(cur_tag) {
-            true
-        } else {
-            false
+    /// Writes `report` into the `reports` folder next to the config file.
+    ///
+    /// # Return
+    /// Returns where the report was saved.
+    pub fn export_billing(&self, report: &BillingReport, format: ReportFormat) -> Result<PathBuf> {
+        let report_dir = self.data_dir.join("reports");
+        fs::create_dir_all(&report_dir).map_err(|e| Error::io(&report_dir, e))?;
+
+        let file_name = format!(
+            "billing-{}-{}.{}",
+            report.from,
+            report.to,
+            format.extension()
+        );
+        let path = report_dir.join(file_name);
+        fs::write(&path, report.export(format)).map_err(|e| Error::io(&path, e))?;
+        Ok(path)
+    }
+
+    /// Saves `edit_notes` as the notes of the entry being edited.
+    pub fn save_notes(&mut self) {
+        let Some(index) = self.notes_entry.take() else {
+            return;
+        };
+
+        self.config = self.read_config_file();
+        match self.check_entry_open(&self.config, index) {
+            Ok(()) => {
+                self.config.entry[index].notes = std::mem::take(&mut self.edit_notes);
+                self.write_config_file();
+            }
+            Err(e) => self.show_error(e),
         }
     }
+
+    /// Replaces the tags of the entry at `index` besides its primary one.
+    pub fn set_extra_tags(&mut self, index: usize, tags: &[String]) {
+        let mut config = self.read_config_file();
+        if let Err(e) = self.check_entry_open(&config, index) {
+            self.show_error(e);
+            return;
+        }
+
+        let preferences = &self.settings.preferences;
+        let tag_indexes = config.ensure_tags(
+            tags,
+            &self.color,
+            preferences.tag_palette,
+            preferences.theme.background(),
+        );
+
+        config.entry[index].set_extra_tags(tag_indexes);
+
+        self.config = config;
+        self.write_config_file();
+    }
+
+    /// Deletes the entry at `index` from the history.
+    pub fn delete_entry(&mut self, index: usize) {
+        self.config = self.read_config_file();
+        if let Err(e) = self.check_entry_open(&self.config, index) {
+            self.show_error(e);
+            return;
+        }
+
+        self.config.entry.remove(index);
+        if index < self.config.total_time.len() {
+            self.config.total_time.remove(index);
+        }
+        self.write_config_file();
+    }
+
+    /// Fails with `Error::WeekSubmitted` if `time` is in a week whose timesheet was
+    /// submitted. Every change to the history is checked with this first.
+    pub fn check_week_open(&self, config: &Config, time: DateTime<Local>) -> Result<()> {
+        if config.is_time_submitted(time, self.settings.preferences.week_start) {
+            Err(Error::WeekSubmitted)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Fails if the entry at `index` doesn't exist, or has a session in a
+    /// submitted week. See `App::check_week_open`.
+    pub fn check_entry_open(&self, config: &Config, index: usize) -> Result<()> {
+        let entry = config.entry.get(index).ok_or(Error::EntryNotFound(index))?;
+        entry
+            .session_starts
+            .iter()
+            .try_for_each(|start| self.check_week_open(config, *start))
+    }
+
+    /// Runs the actions whose shortcuts were pressed this frame.
+    fn handle_shortcuts(&mut self, ctx: &Context) {
+        // Keys pressed while a new shortcut is being recorded, or while a popup
+        // is waiting on the user, are left alone.
+        if self.recording_shortcut.is_some()
+            || self.error.is_some()
+            || self.show_color_picker
+            || self.screen == Screen::Error
+        {
+            return;
+        }
+
+        // Shortcuts without Ctrl or Alt would eat the keys the user is typing.
+        let typing = ctx.wants_keyboard_input();
+        let keymap = &self.settings.preferences.keymap;
+        let actions: Vec<Action> = ctx
+            .input()
+            .events
+            .iter()
+            .filter_map(|event| match event {
+                egui::Event::Key {
+                    key,
+                    pressed: true,
+                    modifiers,
+                } => keymap.action(*key, *modifiers),
+                _ => None,
+            })
+            .filter(|action| {
+                !typing
+                    || !keymap
+                        .shortcut(*action)
+                        .is_some_and(|shortcut| shortcut.is_typing())
+            })
+            .collect();
+
+        for action in actions {
+            self.run_action(action);
+        }
+    }
+
+    /// Does what `action` says, if it makes sense on the current screen.
+    pub fn run_action(&mut self, action: Action) {
+        let tracking = matches!(self.screen, Screen::Tracking | Screen::Pause);
+        let screen = match action {
+            Action::ShowHome => Some(Screen::Start),
+            Action::ShowHistory => Some(Screen::History),
+            Action::ShowTags => Some(Screen::Tags),
+            Action::ShowTimesheet => Some(Screen::Timesheet),
+            Action::ShowBilling => Some(Screen::Billing),
+            Action::ShowSettings => Some(Screen::Settings),
+            _ => None,
+        };
+
+        // The other screens can't be reached until the activity is stopped.
+        if let Some(screen) = screen {
+            if !tracking {
+                self.screen = screen;
+            }
+            return;
+        }
+
+        match action {
+            Action::StartStop => match self.screen {
+                Screen::Start => start_from_home(self),
+                Screen::Tracking | Screen::Pause if self.short_entry_end.is_none() => {
+                    self.add_entry()
+                }
+                _ => {}
+            },
+            Action::PauseResume => match self.screen {
+                Screen::Tracking => self.pause(),
+                Screen::Pause => self.resume(),
+                _ => {}
+            },
+            Action::FocusActivity if !tracking => {
+                self.screen = Screen::Start;
+                self.focus_activity = true;
+            }
+            Action::NextRow => self.move_history_row(true),
+            Action::PreviousRow => self.move_history_row(false),
+            Action::RenameRow => {
+                if let Some((index, entry)) = self.selected_entry() {
+                    self.new_name = entry.name;
+                    self.target_name_index = index;
+                    self.show_name_assign_dialog = true;
+                    self.focus = false;
+                }
+            }
+            Action::RetagRow => {
+                if let Some((index, entry)) = self.selected_entry() {
+                    let tag_list = self.read_config_file().tag_list;
+                    let tag_name = |tag_index: &usize| {
+                        tag_list.get(*tag_index).map(|tag| tag.trim().to_string())
+                    };
+
+                    self.new_tag = tag_name(&entry.tag_index).unwrap_or_default();
+                    self.retag_extra_tags = entry.extra_tags.iter().filter_map(tag_name).collect();
+                    self.new_tag_color = None;
+                    self.target_tag_index = index;
+                    self.show_change_tag_win = true;
+                }
+            }
+            Action::DeleteRow => {
+                if let Some((index, _)) = self.selected_entry() {
+                    self.delete_entry(index);
+                    let count = self.config.entry.len();
+                    self.history_row = (count > 0).then(|| index.min(count - 1));
+                }
+            }
+            Action::ShowShortcuts => self.show_shortcuts = !self.show_shortcuts,
+            Action::CommandPalette => {
+                self.show_palette = !self.show_palette;
+                self.palette_query.clear();
+                self.palette_selected = 0;
+            }
+            _ => {}
+        }
+    }
+
+    /// Starts or stops the API server to match the settings, then answers the
+    /// requests that came in since the last frame.
+    fn update_api(&mut self, ctx: &Context) {
+        let preferences = &self.settings.preferences;
+        let wanted = (preferences.api_enabled && !preferences.api_token.is_empty())
+            .then(|| (preferences.api_port, preferences.api_token.clone()));
+        // A new token doesn't need the port to be opened again.
+        if let (Some(api), Some((port, token))) = (&mut self.api, &wanted) {
+            if api.port == *port && api.token != *token {
+                api.set_token(token.clone());
+            }
+        }
+
+        let running = self.api.as_ref().map(|api| (api.port, api.token.clone()));
+        if wanted != running {
+            self.api = None;
+            if wanted.is_none() {
+                self.api_failed = None;
+            }
+            if let Some((port, token)) = wanted {
+                if self.api_failed.as_ref() != Some(&(port, token.clone())) {
+                    match ApiServer::start(port, token.clone(), ctx.clone()) {
+                        Ok(api) => {
+                            self.api = Some(api);
+                            self.api_failed = None;
+                        }
+                        Err(source) => {
+                            self.api_failed = Some((port, token));
+                            self.show_error(Error::Api { port, source });
+                        }
+                    }
+                }
+            }
+        }
+
+        let requests: Vec<ApiRequest> = match &self.api {
+            Some(api) => api.requests.try_iter().collect(),
+            None => return,
+        };
+        self.answer_requests(requests);
+    }
+
+    /// Opens or closes the control socket to match the settings, then answers
+    /// the commands that came in since the last frame.
+    fn update_socket(&mut self, ctx: &Context) {
+        let wanted = self.settings.preferences.control_socket;
+        if !wanted {
+            self.socket = None;
+            self.socket_failed = false;
+        } else if self.socket.is_none() && !self.socket_failed {
+            // Without a runtime folder there's nowhere safe to put the socket.
+            self.socket_failed = true;
+            if let Some(path) = socket_path() {
+                match SocketServer::start(path.clone(), ctx.clone()) {
+                    Ok(socket) => {
+                        self.socket = Some(socket);
+                        self.socket_failed = false;
+                    }
+                    // Another actt is already answering there, which is fine.
+                    Err(source) if source.kind() == io::ErrorKind::AddrInUse => {}
+                    Err(source) => self.show_error(Error::Socket { path, source }),
+                }
+            }
+        }
+
+        let requests: Vec<ApiRequest> = match &self.socket {
+            Some(socket) => socket.requests.try_iter().collect(),
+            None => return,
+        };
+        self.answer_requests(requests);
+    }
+
+    fn answer_requests(&mut self, requests: Vec<ApiRequest>) {
+        for (command, reply) in requests {
+            let answer = self.answer_api(command);
+            // The script may have given up waiting already.
+            let _ = reply.send(answer);
+        }
+    }
+
+    /// Does what a script asked for through the API.
+    pub fn answer_api(&mut self, command: ApiCommand) -> ApiReply {
+        if self.screen == Screen::Error {
+            return ApiReply::error(503, "actt can't use its data folder");
+        }
+
+        let tracking = matches!(self.screen, Screen::Tracking | Screen::Pause);
+        match command {
+            ApiCommand::Status => ApiReply::ok(self.api_status()),
+            ApiCommand::Start { name, tag } => {
+                if tracking {
+                    return ApiReply::error(409, "An activity is already being tracked");
+                }
+
+                let name = name.trim().to_string();
+                if name.is_empty() {
+                    return ApiReply::error(400, &Error::EmptyActivityName.to_string());
+                }
+
+                if let Err(e) = self.check_week_open(&self.read_config_file(), Local::now()) {
+                    return ApiReply::error(409, &e.to_string());
+                }
+
+                self.quick_start(Favorite::new(name, tag.trim().to_string()));
+                ApiReply::ok(self.api_status())
+            }
+            ApiCommand::Pause => {
+                if self.screen != Screen::Tracking {
+                    return ApiReply::error(409, "Nothing is being tracked");
+                }
+
+                self.pause();
+                ApiReply::ok(self.api_status())
+            }
+            ApiCommand::Resume => {
+                if self.screen != Screen::Pause {
+                    return ApiReply::error(409, "Nothing is paused");
+                }
+
+                self.resume();
+                ApiReply::ok(self.api_status())
+            }
+            ApiCommand::Stop => {
+                if !tracking {
+                    return ApiReply::error(409, "Nothing is being tracked");
+                }
+
+                let started_at = self.started_at.unwrap_or_else(Local::now);
+                if let Err(e) = self.check_week_open(&self.read_config_file(), started_at) {
+                    return ApiReply::error(409, &e.to_string());
+                }
+
+                // There is nobody to ask about short entries, so they're saved
+                // the same way as any other.
+                let name = self.activity_name.clone();
+                self.short_entry_end = None;
+                self.add_entry_at(Instant::now());
+                ApiReply::ok(json!({ "state": "idle", "stopped": name }))
+            }
+            ApiCommand::Entries => {
+                let config = self.read_config_file();
+                let entries: Vec<Value> = config
+                    .entry
+                    .iter()
+                    .map(|entry| {
+                        json!({
+                            "name": entry.name,
+                            "tag": config.tag_list.get(entry.tag_index).map(|tag| tag.trim()),
+                            "extra_tags": config.extra_tag_names(entry),
+                            "started_at": entry.started_at().map(|time| time.to_rfc3339()),
+                            "work_seconds": entry.work_time.as_secs(),
+                            "pause_seconds": entry.pause_time.as_secs(),
+                            "billable": entry.billable,
+                            "notes": entry.notes,
+                        })
+                    })
+                    .collect();
+                ApiReply::ok(Value::from(entries))
+            }
+            ApiCommand::Tags => {
+                let config = self.read_config_file();
+                let tags: Vec<Value> = config
+                    .tag_list
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, tag)| !tag.trim().is_empty())
+                    .map(|(tag_index, tag)| {
+                        let color = config.colors.get(tag_index).map(|color| {
+                            format!("#{:02x}{:02x}{:02x}", color.r(), color.g(), color.b())
+                        });
+                        json!({ "name": tag.trim(), "color": color })
+                    })
+                    .collect();
+                ApiReply::ok(Value::from(tags))
+            }
+        }
+    }
+
+    /// What is being tracked right now, for the API.
+    fn api_status(&mut self) -> Value {
+        let state = match self.screen {
+            Screen::Tracking => "tracking",
+            Screen::Pause => "paused",
+            _ => return json!({ "state": "idle" }),
+        };
+
+        self.update_work_time();
+        json!({
+            "state": state,
+            "activity": self.activity_name,
+            "tag": self.tag_name.trim(),
+            "started_at": self.started_at.map(|time| time.to_rfc3339()),
+            "elapsed_seconds": self.work_time.as_secs(),
+            "paused_seconds": self.paused_time().as_secs(),
+        })
+    }
+
+    /// Everything the command palette can offer right now.
+    pub fn palette_commands(&self) -> Vec<Command> {
+        let tracking = matches!(self.screen, Screen::Tracking | Screen::Pause);
+        let config = self.read_config_file();
+        let recent = config.recent_activities(PALETTE_RECENT_LIMIT);
+        let mut commands: Vec<Command> = recent.into_iter().map(Command::Start).collect();
+
+        let actions = Action::ALL
+            .into_iter()
+            .filter(|action| *action != Action::CommandPalette);
+        commands.extend(actions.map(Command::Action));
+
+        // Asking for a tag opens the history screen, which can't be done while tracking.
+        if !tracking && !config.entry.is_empty() {
+            commands.push(Command::RetagLast(None));
+        }
+
+        for format in [
+            ReportFormat::Csv,
+            ReportFormat::Markdown,
+            ReportFormat::Html,
+        ] {
+            commands.push(Command::Export(format));
+        }
+        commands.push(Command::ToggleTheme);
+
+        commands
+    }
+
+    /// Runs a command picked from the command palette. Starting an activity while
+    /// another one is tracked switches to it, see `App::switch_activity`.
+    pub fn run_command(&mut self, command: Command) {
+        let tracking = matches!(self.screen, Screen::Tracking | Screen::Pause);
+        match command {
+            Command::Action(action) => self.run_action(action),
+            Command::Start(activity) if tracking => {
+                self.next_activity_name = activity.name;
+                self.next_tag_name = activity.tag;
+                self.switch_activity();
+            }
+            Command::Start(activity) => self.quick_start(activity),
+            Command::RetagLast(tag) => {
+                let Some(index) = self.read_config_file().entry.len().checked_sub(1) else {
+                    return;
+                };
+
+                match tag {
+                    Some(tag) => {
+                        self.new_tag = tag;
+                        self.create_tag(index, None);
+                    }
+                    None if !tracking => {
+                        self.screen = Screen::History;
+                        self.history_row = Some(index);
+                        self.scroll_to_row = true;
+                        self.run_action(Action::RetagRow);
+                    }
+                    None => {}
+                }
+            }
+            Command::Export(format) => {
+                let Some((from, to)) = self.billing_dates() else {
+                    let msg = "The billing dates have to be written as YYYY-MM-DD.";
+                    self.warning = Some(msg.to_string());
+                    return;
+                };
+
+                let config = self.read_config_file();
+                let report = BillingReport::new(&config, &self.settings.preferences, from, to);
+                match self.export_billing(&report, format) {
+                    Ok(path) => self.warning = Some(format!("Saved to {}", path.display())),
+                    Err(e) => self.show_error(e),
+                }
+            }
+            Command::ToggleTheme => {
+                let preferences = &mut self.settings.preferences;
+                preferences.theme = match preferences.theme {
+                    Theme::Dark => Theme::Light,
+                    Theme::Light => Theme::Dark,
+                };
+
+                if let Err(e) = self.write_settings_file() {
+                    self.show_error(e);
+                }
+            }
+        }
+    }
+
+    /// The entry picked with the keyboard, while the history screen is shown.
+    fn selected_entry(&self) -> Option<(usize, Entry)> {
+        if self.screen != Screen::History {
+            return None;
+        }
+
+        let index = self.history_row?;
+        let entry = self.read_config_file().entry.get(index)?.clone();
+        Some((index, entry))
+    }
+
+    /// Picks the next or previous entry shown on the history screen.
+    fn move_history_row(&mut self, forward: bool) {
+        if self.screen != Screen::History {
+            return;
+        }
+
+        let config = self.read_config_file();
+        let search = self.history_search.trim();
+        let shown: Vec<usize> = (0..config.entry.len())
+            .filter(|index| {
+                search.is_empty() || config.entry_matches(&config.entry[*index], search)
+            })
+            .collect();
+        if shown.is_empty() {
+            return;
+        }
+
+        let position = self
+            .history_row
+            .and_then(|row| shown.iter().position(|index| *index == row));
+        let position = match (position, forward) {
+            (Some(position), true) => (position + 1).min(shown.len() - 1),
+            (Some(position), false) => position.saturating_sub(1),
+            (None, true) => 0,
+            (None, false) => shown.len() - 1,
+        };
+
+        self.history_row = Some(shown[position]);
+        self.scroll_to_row = true;
+    }
+
+    /// Pauses the current activity. Pausing an activity that is already
+    /// paused keeps the original pause going.
+    pub fn pause(&mut self) {
+        if self.pause_time.is_none() {
+            self.pause_time = Some(Instant::now());
+        }
+
+        self.screen = Screen::Pause;
+    }
+
+    /// Resumes the current activity.
+    pub fn resume(&mut self) {
+        self.end_pause(Instant::now());
+        self.screen = Screen::Tracking;
+    }
+
+    /// Records the current pause as having ended at `end`, if there is one.
+    fn end_pause(&mut self, end: Instant) {
+        if let Some(pause_time) = self.pause_time.take() {
+            let pause = end.saturating_duration_since(pause_time);
+            self.pauses.push(pause);
+            self.total_pause_time += pause;
+        }
+    }
+
+    /// How long the current activity has been paused for, including the
+    /// pause that is still going on.
+    pub fn paused_time(&self) -> Duration {
+        match self.pause_time {
+            Some(pause_time) => self.total_pause_time + pause_time.elapsed(),
+            None => self.total_pause_time,
+        }
+    }
+
+    /// Recalculates `work_time` from the time the activity started, minus
+    /// the time spent paused.
+    pub fn update_work_time(&mut self) {
+        if let Some(total_time) = self.total_time {
+            self.work_time = total_time.elapsed().saturating_sub(self.paused_time());
+        }
+    }
+
+    /// Starts tracking the current activity from `start`.
+    pub fn start_activity(&mut self, start: Instant) {
+        // Nothing can be tracked into a submitted week.
+        if let Err(e) = self.check_week_open(&self.read_config_file(), Local::now()) {
+            self.show_error(e);
+            return;
+        }
+
+        self.total_time = Some(start);
+        self.started_at = chrono::Duration::from_std(start.elapsed())
+            .ok()
+            .map(|elapsed| Local::now() - elapsed);
+        self.pause_time = None;
+        self.pauses.clear();
+        self.total_pause_time = Duration::default();
+        self.work_time = Duration::default();
+        self.resume_index = None;
+        self.planned_time = None;
+        self.last_input = Some(start);
+        self.idle_since = None;
+        self.idle_interval = None;
+        self.pomodoro_phase = PomodoroPhase::Work;
+        self.pomodoro_mark = Duration::default();
+        self.pomodoro_cycles = 0;
+        self.pomodoro_alert = None;
+        self.screen = Screen::Tracking;
+    }
+
+    /// Time-boxes the current activity with the planned time from the start screen.
+    pub fn apply_plan(&mut self) {
+        self.planned_time = if self.planned_minutes == 0 {
+            None
+        } else {
+            Some(Duration::from_secs(self.planned_minutes * 60))
+        };
+    }
+
+    /// Stops the activity if it is time-boxed, has run out of time and the user
+    /// asked for it to be stopped automatically.
+    pub fn check_plan(&mut self) {
+        match self.planned_time {
+            Some(planned_time) if self.auto_stop && self.work_time >= planned_time => {
+                self.add_entry();
+            }
+            _ => (),
+        }
+    }
+
+    /// How much time was spent towards `goal` of the tag at `tag_index` in the
+    /// current day or week, including the activity being tracked right now.
+    pub fn goal_usage(&self, tag_index: usize, goal: &Goal) -> Duration {
+        let now = Local::now();
+        let week_start = self.settings.preferences.week_start.num_days_from_monday();
+        let from = goal.period_start(now, week_start);
+        let mut used = self.config.tag_time_between(tag_index, from, now);
+
+        let current_tag_index = self.config.find_tag(&self.config.tag_list, &self.tag_name);
+        let is_tracking = self.started_at.is_some_and(|started_at| from <= started_at);
+        if is_tracking && current_tag_index == Some(tag_index) {
+            used += self.work_time;
+        }
+
+        used
+    }
+
+    /// A warning for when a budget of the tag at `tag_index` is close to being
+    /// used up or has already been exceeded.
+    pub fn goal_warning(&self, tag_index: usize) -> Option<String> {
+        let tag = self.config.tag_list.get(tag_index)?.trim();
+
+        for goal in self.config.goals(tag_index) {
+            if goal.kind != GoalKind::AtMost {
+                continue;
+            }
+
+            let used = self.goal_usage(tag_index, goal);
+            let progress = goal.progress(used);
+            if progress >= 1.0 {
+                return Some(format!(
+                    "{} is over its budget of {}, {} used.",
+                    tag,
+                    goal.describe(),
+                    self.format_time(used)
+                ));
+            } else if progress >= GOAL_WARNING_THRESHOLD {
+                return Some(format!(
+                    "{} is close to its budget of {}, {} used.",
+                    tag,
+                    goal.describe(),
+                    self.format_time(used)
+                ));
+            }
+        }
+
+        None
+    }
+
+    /// Watches for the user going idle while an activity is running. Once they
+    /// come back they are asked what to do with the time they were away.
+    pub fn update_idle(&mut self, ctx: &Context) {
+        let threshold = Duration::from_secs(self.settings.preferences.idle_threshold_minutes * 60);
+        let now = Instant::now();
+
+        // Idle time while paused isn't counted anyway.
+        let ignore = threshold.is_zero() || self.screen == Screen::Pause;
+        if ignore || self.idle_interval.is_some() {
+            self.last_input = Some(now);
+            self.idle_since = None;
+            return;
+        }
+
+        let idle_for = match self.idle_monitor.system_idle_time() {
+            Some(idle_for) => idle_for,
+            None => {
+                // Without the desktop session to ask, only input to actt can be seen.
+                // The user could be busy in another window, so that isn't idle.
+                let input = ctx.input();
+                if !input.raw.has_focus || !input.events.is_empty() {
+                    self.last_input = Some(now);
+                }
+
+                now.saturating_duration_since(self.last_input.unwrap_or(now))
+            }
+        };
+
+        match self.idle_since {
+            None if idle_for >= threshold => {
+                self.idle_since = Some(now - idle_for);
+            }
+            Some(idle_since) if idle_for < threshold => {
+                self.idle_since = None;
+                self.idle_interval = Some((idle_since, now - idle_for));
+            }
+            _ => (),
+        }
+    }
+
+    /// Keeps the idle time as part of the current activity.
+    pub fn keep_idle_time(&mut self) {
+        self.idle_interval = None;
+    }
+
+    /// Removes the idle time from the current activity by counting it as a pause.
+    pub fn discard_idle_time(&mut self) {
+        if let Some((start, end)) = self.idle_interval.take() {
+            let idle_time = end.saturating_duration_since(start);
+            self.pauses.push(idle_time);
+            self.total_pause_time += idle_time;
+        }
+    }
+
+    /// Moves the idle time out of the current activity into an entry of its own,
+    /// named `idle_activity_name` and tagged with `idle_tag_name`.
+    pub fn reassign_idle_time(&mut self) {
+        let Some((start, end)) = self.idle_interval else {
+            return;
+        };
+
+        let idle_time = end.saturating_duration_since(start);
+        let started_at = chrono::Duration::from_std(start.elapsed())
+            .map(|elapsed| Local::now() - elapsed)
+            .unwrap_or_else(|_| Local::now());
+
+        let mut tag = std::mem::take(&mut self.idle_tag_name);
+        if tag.is_empty() {
+            tag = EMPTY_TAG.to_string();
+        }
+
+        let mut config = self.read_config_file();
+        if let Err(e) = self.check_week_open(&config, started_at) {
+            self.show_error(e);
+            return;
+        }
+
+        let preferences = &self.settings.preferences;
+        let (tag_index, color_index) = config.ensure_tag(
+            &tag,
+            &self.color,
+            preferences.tag_palette,
+            preferences.theme.background(),
+        );
+        let entry = Entry::new(
+            std::mem::take(&mut self.idle_activity_name),
+            tag_index,
+            color_index,
+            started_at,
+            idle_time,
+            Vec::new(),
+        );
+        config.entry.push(entry);
+        config.total_time.push(idle_time);
+
+        self.config = config;
+        self.write_config_file();
+        self.discard_idle_time();
+    }
+
+    /// Moves between pomodoro phases once the current one runs out. Breaks are
+    /// counted as paused time, so a break pauses the activity and resumes it once over.
+    pub fn update_pomodoro(&mut self) {
+        if !self.pomodoro {
+            return;
+        }
+
+        let pomodoro = self.settings.preferences.pomodoro.clone();
+        let phase_length = self.pomodoro_phase.length(&pomodoro);
+
+        match self.pomodoro_phase {
+            PomodoroPhase::Work => {
+                if self.pomodoro_elapsed() < phase_length {
+                    return;
+                }
+
+                self.pomodoro_cycles += 1;
+                let long_break = self
+                    .pomodoro_cycles
+                    .is_multiple_of(pomodoro.long_break_every);
+                self.pomodoro_phase = if long_break {
+                    PomodoroPhase::LongBreak
+                } else {
+                    PomodoroPhase::ShortBreak
+                };
+
+                self.pause();
+                self.pomodoro_alert("Time for a break!", pomodoro.play_sound);
+            }
+            PomodoroPhase::ShortBreak | PomodoroPhase::LongBreak => {
+                match self.pause_time {
+                    // The user cut the break short.
+                    None => self.start_pomodoro_work(),
+                    Some(pause_time) if pause_time.elapsed() >= phase_length => {
+                        self.resume();
+                        self.start_pomodoro_work();
+                        self.pomodoro_alert("Break's over, back to work!", pomodoro.play_sound);
+                    }
+                    Some(_) => (),
+                }
+            }
+        }
+    }
+
+    /// How long the user has been in the current pomodoro phase.
+    pub fn pomodoro_elapsed(&self) -> Duration {
+        match (self.pomodoro_phase, self.pause_time) {
+            (PomodoroPhase::Work, _) => self.work_time.saturating_sub(self.pomodoro_mark),
+            (_, Some(pause_time)) => pause_time.elapsed(),
+            (_, None) => Duration::default(),
+        }
+    }
+
+    /// Starts a new pomodoro work phase from the current `work_time`.
+    pub fn start_pomodoro_work(&mut self) {
+        self.pomodoro_phase = PomodoroPhase::Work;
+        self.pomodoro_mark = self.work_time;
+    }
+
+    fn pomodoro_alert(&mut self, msg: &str, play_sound: bool) {
+        self.pomodoro_alert = Some(msg.to_string());
+        if play_sound {
+            play_alert_sound();
+        }
+    }
+
+    /// Starts a new session of the activity in the entry at `index`. If `append` is
+    /// true the time is added onto that entry, otherwise a new entry is created.
+    pub fn resume_entry(&mut self, index: usize, append: bool) {
+        let config = self.read_config_file();
+        let Some(entry) = config.entry.get(index) else {
+            return;
+        };
+
+        // Entries of submitted weeks can't grow, the activity gets a new one.
+        let locked = self.check_entry_open(&config, index).is_err();
+        let append = append && !locked;
+
+        self.activity_name = entry.name.clone();
+        self.tag_name = match config.tag_list.get(entry.tag_index) {
+            Some(tag) if !tag.trim().is_empty() => tag.clone(),
+            _ => EMPTY_TAG.to_string(),
+        };
+        self.extra_tags = config.extra_tag_names(entry);
+        self.notes = if append {
+            entry.notes.clone()
+        } else {
+            String::new()
+        };
+        self.billable = entry.billable;
+        if let Some(color) = config.colors.get(entry.color_index) {
+            self.color = *color;
+        }
+
+        self.warning = locked.then(|| {
+            "That entry's week was submitted, so this is tracked as a new entry.".to_string()
+        });
+        self.start_activity(Instant::now());
+        if append {
+            self.resume_index = Some(index);
+        }
+    }
+
+    /// Ends the current activity and starts `next_activity_name` under
+    /// `next_tag_name` at the exact moment the previous one ended.
+    pub fn switch_activity(&mut self) {
+        // The current activity keeps going if it can't be saved or nothing new
+        // can be started.
+        let config = self.read_config_file();
+        let started_at = self.started_at.unwrap_or_else(Local::now);
+        let open = self
+            .check_week_open(&config, started_at)
+            .and_then(|_| self.check_week_open(&config, Local::now()));
+        if let Err(e) = open {
+            self.show_error(e);
+            return;
+        }
+
+        let now = Instant::now();
+        self.add_entry_at(now);
+
+        self.activity_name = std::mem::take(&mut self.next_activity_name);
+        self.tag_name = std::mem::take(&mut self.next_tag_name);
+        if self.tag_name.is_empty() {
+            self.tag_name = EMPTY_TAG.to_string();
+        }
+
+        self.extra_tags.clear();
+        self.reset_activity_details();
+        self.color = self.tag_color(&self.tag_name);
+        self.show_switch_win = false;
+        self.start_activity(now);
+    }
+
+    /// The color to use for `tag`. Existing tags keep their color, new ones
+    /// get one that isn't taken yet.
+    pub fn tag_color(&self, tag: &str) -> Color32 {
+        match self.config.find_tag(&self.config.tag_list, tag) {
+            Some(tag_index) => self
+                .config
+                .colors
+                .get(tag_index)
+                .copied()
+                .unwrap_or(self.color),
+            None => self.pick_tag_color(&self.config.colors, &self.color),
+        }
+    }
+
+    /// Picks a color for a new tag from the user's palette, see `Config::pick_color`.
+    pub fn pick_tag_color(&self, list_of_colors: &[Color32], color: &Color32) -> Color32 {
+        let preferences = &self.settings.preferences;
+        self.config.pick_color(
+            list_of_colors,
+            color,
+            preferences.tag_palette,
+            preferences.theme.background(),
+        )
+    }
+
+    /// Starts `activity` straight away, skipping the start screen form.
+    pub fn quick_start(&mut self, activity: Favorite) {
+        self.activity_name = activity.name;
+        self.tag_name = if activity.tag.is_empty() {
+            EMPTY_TAG.to_string()
+        } else {
+            activity.tag
+        };
+
+        self.extra_tags.clear();
+        self.reset_activity_details();
+        self.color = self.tag_color(&self.tag_name);
+        self.warning = None;
+        self.start_activity(Instant::now());
+        self.apply_plan();
+    }
+
+    /// Forgets the notes and billable state of the previous activity, so a new
+    /// one doesn't carry them over.
+    pub fn reset_activity_details(&mut self) {
+        self.notes.clear();
+        self.billable = true;
+    }
+
+    /// Pins `activity` to the start screen, or unpins it if it already is.
+    pub fn toggle_favorite(&mut self, activity: Favorite) {
+        self.config = self.read_config_file();
+        match self.config.favorites.iter().position(|e| *e == activity) {
+            Some(index) => {
+                self.config.favorites.remove(index);
+            }
+            None => self.config.favorites.push(activity),
+        }
+
+        self.write_config_file();
+    }
+
+    /// Adds the details of an activity to `Config`.
+    pub fn add_entry(&mut self) {
+        let now = Instant::now();
+        let preferences = &self.settings.preferences;
+        let min_time = Duration::from_secs(preferences.min_entry_seconds);
+        if preferences.confirm_short_entries
+            && self.work_time >= min_time
+            && self.work_time < Duration::from_secs(60)
+        {
+            self.short_entry_end = Some(now);
+            return;
+        }
+
+        self.add_entry_at(now);
+    }
+
+    /// Throws the current activity away without saving it.
+    pub fn discard_entry(&mut self) {
+        self.screen = Screen::History;
+        self.resume_index = None;
+        self.pauses.clear();
+        self.notes.clear();
+        self.reset_timers();
+    }
+
+    /// Adds the details of an activity that ended at `end` to `Config`.
+    pub fn add_entry_at(&mut self, end: Instant) {
+        // The activity keeps going if its session would land in a submitted week.
+        let started_at = self.started_at.unwrap_or_else(Local::now);
+        let config = self.read_config_file();
+        if let Err(e) = self.check_week_open(&config, started_at) {
+            self.show_error(e);
+            return;
+        }
+
+        // An entry whose week was submitted while it was being resumed gets the
+        // session as a new entry instead.
+        if let Some(index) = self.resume_index {
+            if self.check_entry_open(&config, index).is_err() {
+                self.resume_index = None;
+            }
+        }
+
+        // Logic for adding entries to the config file.
+        self.screen = Screen::History;
+        self.end_pause(end);
+        if let Some(total_time) = self.total_time {
+            self.work_time = end
+                .saturating_duration_since(total_time)
+                .saturating_sub(self.total_pause_time);
+        }
+
+        let preferences = &self.settings.preferences;
+        let min_time = Duration::from_secs(preferences.min_entry_seconds);
+        if self.work_time < min_time {
+            self.warning = Some(format!(
+                "\"{}\" was shorter than {} seconds, so it wasn't saved.",
+                self.activity_name, preferences.min_entry_seconds
+            ));
+            self.discard_entry();
+            return;
+        }
+
+        // An entry that was long enough to keep isn't rounded away to nothing.
+        let rounded = preferences
+            .entry_rounding
+            .apply(self.work_time, preferences.entry_rounding_minutes);
+        self.work_time = if rounded.is_zero() && !self.work_time.is_zero() {
+            Duration::from_secs(preferences.entry_rounding_minutes * 60)
+        } else {
+            rounded
+        };
+
+        // TODO: Find a way to make checks for if preferences were changed
+        let mut config = self.read_config_file();
+        let pauses = std::mem::take(&mut self.pauses);
+
+        if let Some(index) = self.resume_index.take() {
+            if let Some(entry) = config.entry.get_mut(index) {
+                entry.add_session(started_at, self.work_time, pauses);
+                entry.notes = std::mem::take(&mut self.notes);
+                if let Some(total_time) = config.total_time.get_mut(index) {
+                    *total_time += self.work_time;
+                }
+
+                self.finish_entry(config);
+                return;
+            }
+        }
+
+        config.total_time.push(self.work_time);
+
+        if config.find_tag(&config.tag_list, &self.tag_name).is_some() {
+            // Existing tags keep their own color.
+            let preferences = &self.settings.preferences;
+            let (tag_index, color_index) = config.ensure_tag(
+                &self.tag_name,
+                &self.color,
+                preferences.tag_palette,
+                preferences.theme.background(),
+            );
+
+            let new_entry = Entry::new(
+                self.activity_name.clone(),
+                tag_index,
+                color_index,
+                started_at,
+                self.work_time,
+                pauses,
+            );
+            config.entry.push(new_entry);
+        } else {
+            // If true means a color already exists. There can't be clashing colors for
+            // tags. Therefore one from the palette will be assigned.
+            if self.settings.preferences.tag_assign_behavior == TagAssignBehavior::Random
+                && self
+                    .config
+                    .find_color(&config.colors, &self.color)
+                    .is_some()
+            {
+                self.color = self.pick_tag_color(&config.colors, &self.color);
+            }
+
+            config.colors.push(self.color.clone());
+            let new_color_index = config.colors.len() - 1;
+
+            let new_tag_index = config.tag_list.len();
+            let entry = Entry::new(
+                self.activity_name.clone(),
+                new_tag_index,
+                new_color_index,
+                started_at,
+                self.work_time,
+                pauses,
+            );
+            config.entry.push(entry);
+
+            config.tag_list.push(self.tag_name.clone());
+        }
+
+        // config.set_tag_assign_behavior(self.tag_assign_behavior.clone());
+
+        // The other tags are added once the primary tag exists, so it keeps its index.
+        let preferences = &self.settings.preferences;
+        let extra_tags = config.ensure_tags(
+            &self.extra_tags,
+            &self.color,
+            preferences.tag_palette,
+            preferences.theme.background(),
+        );
+        if let Some(entry) = config.entry.last_mut() {
+            entry.set_extra_tags(extra_tags);
+            entry.notes = std::mem::take(&mut self.notes);
+            entry.billable = self.billable;
+        }
+
+        self.finish_entry(config);
+    }
+
+    /// Saves `config` once an entry has been added and clears the timers.
+    fn finish_entry(&mut self, config: Config) {
+        self.config = config;
+        self.write_config_file();
+        self.reset_timers();
+    }
+
+    fn reset_timers(&mut self) {
+        self.pause_time = None;
+        self.total_time = None;
+        self.started_at = None;
+        self.total_pause_time = Duration::default();
+        self.work_time = Duration::default();
+    }
+
+    // TODO: This should be inside the config utilities module
+    pub fn does_tag_exist(&self, tag_list: &[String], cur_tag: &str) -> bool {
+        self.config.find_tag(tag_list, cur_tag).is_some()
+    }
+}
+
+/// Plays the desktop's alert sound with whichever player the system has, so no
+/// audio library is needed. It plays in the background and failures are ignored.
+fn play_alert_sound() {
+    let players: &[(&str, &[&str])] = if cfg!(target_os = "macos") {
+        &[("afplay", &["/System/Library/Sounds/Glass.aiff"])]
+    } else if cfg!(windows) {
+        &[(
+            "powershell",
+            &[
+                "-NoProfile",
+                "-Command",
+                "[System.Media.SystemSounds]::Asterisk.Play()",
+            ],
+        )]
+    } else {
+        &[
+            ("canberra-gtk-play", &["--id", "complete"]),
+            (
+                "paplay",
+                &["/usr/share/sounds/freedesktop/stereo/complete.oga"],
+            ),
+        ]
+    };
+
+    // Waiting for the player on its own thread means it's cleaned up after.
+    std::thread::spawn(move || {
+        for (player, args) in players {
+            let played = std::process::Command::new(player)
+                .args(*args)
+                .status()
+                .is_ok_and(|status| status.success());
+            if played {
+                break;
+            }
+        }
+    });
 }
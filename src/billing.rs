@@ -0,0 +1,402 @@
+use crate::user::{Config, Entry, Preferences, Rate, TagNode};
+
+use std::time::Duration;
+
+use chrono::{DateTime, Days, Local, NaiveDate, NaiveTime, TimeZone};
+
+/// The file formats a billing report can be exported to.
+#[derive(Clone, Copy, PartialEq)]
+pub enum ReportFormat {
+    Csv,
+    Markdown,
+    Html,
+}
+
+impl ReportFormat {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Csv => "CSV",
+            Self::Markdown => "Markdown",
+            Self::Html => "HTML",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Markdown => "md",
+            Self::Html => "html",
+        }
+    }
+}
+
+/// One billed entry.
+pub struct BillingItem {
+    pub date: Option<NaiveDate>,
+    pub activity: String,
+    /// The tag the entry is billed under.
+    pub tag: String,
+    /// The billed time, after rounding.
+    pub time: Duration,
+    pub rate: Rate,
+    pub notes: String,
+}
+
+impl BillingItem {
+    pub fn amount(&self) -> f64 {
+        self.rate.amount(self.time)
+    }
+}
+
+/// The billed time and amount of one tag.
+pub struct BillingLine {
+    pub tag: String,
+    pub time: Duration,
+    pub rate: Rate,
+    pub amount: f64,
+}
+
+/// The billed time and amount of a parent tag, including every tag below it.
+pub struct BillingRollup {
+    pub tag: String,
+    /// How many parents the tag has.
+    pub depth: usize,
+    pub time: Duration,
+    /// The amount in each currency.
+    pub amounts: Vec<(String, f64)>,
+}
+
+impl BillingRollup {
+    /// The amounts, e.g. `"120.00 EUR, 80.00 USD"`.
+    pub fn describe_amounts(&self) -> String {
+        self.amounts
+            .iter()
+            .map(|(currency, amount)| format!("{:.2} {}", amount, currency))
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+}
+
+/// What is billed for each tag between two dates.
+pub struct BillingReport {
+    pub from: NaiveDate,
+    /// The last day of the report, inclusive.
+    pub to: NaiveDate,
+    pub items: Vec<BillingItem>,
+    pub lines: Vec<BillingLine>,
+}
+
+impl BillingReport {
+    /// Bills every billable entry with time in `from..=to`. Each entry is billed
+    /// once, under the first of its tags that has a rate, with its time rounded
+    /// the way `preferences` says.
+    pub fn new(config: &Config, preferences: &Preferences, from: NaiveDate, to: NaiveDate) -> Self {
+        let start = Local
+            .from_local_datetime(&from.and_time(NaiveTime::MIN))
+            .earliest()
+            .unwrap_or_else(Local::now);
+        let end = to
+            .checked_add_days(Days::new(1))
+            .and_then(|end| {
+                Local
+                    .from_local_datetime(&end.and_time(NaiveTime::MIN))
+                    .earliest()
+            })
+            .unwrap_or_else(Local::now);
+
+        // Entries from before start times were recorded have no date, but they
+        // are older than every entry that has one.
+        let first_start = config.entry.iter().filter_map(Entry::started_at).min();
+        let covers_undated = first_start.is_none_or(|first_start| start <= first_start);
+
+        let mut items = Vec::new();
+        let mut lines: Vec<BillingLine> = Vec::new();
+        for entry in config.entry.iter().filter(|entry| entry.billable) {
+            let time = billed_time(entry, start, end, covers_undated);
+            if time.is_zero() {
+                continue;
+            }
+
+            let Some((rate_index, rate)) = entry
+                .tag_indexes()
+                .find_map(|tag_index| config.billing_rate(tag_index))
+            else {
+                continue;
+            };
+
+            let tag = config.tag_list[rate_index].trim().to_string();
+            let time = preferences
+                .billing_rounding
+                .apply(time, preferences.billing_increment_minutes);
+            let item = BillingItem {
+                date: entry.started_at().map(|started_at| started_at.date_naive()),
+                activity: entry.name.clone(),
+                tag: tag.clone(),
+                time,
+                rate: rate.clone(),
+                notes: entry.notes.clone(),
+            };
+
+            match lines.iter_mut().find(|line| line.tag == tag) {
+                Some(line) => {
+                    line.time += time;
+                    line.amount += item.amount();
+                }
+                None => lines.push(BillingLine {
+                    tag,
+                    time,
+                    rate: rate.clone(),
+                    amount: item.amount(),
+                }),
+            }
+            items.push(item);
+        }
+
+        Self {
+            from,
+            to,
+            items,
+            lines,
+        }
+    }
+
+    /// The total amount in each currency.
+    pub fn totals(&self) -> Vec<(String, f64)> {
+        sum_by_currency(self.lines.iter())
+    }
+
+    /// The totals of every tag that has billed tags below it, like `client` for
+    /// `client/project`, in the order of the tag tree.
+    pub fn rollups(&self) -> Vec<BillingRollup> {
+        let tags: Vec<String> = self.lines.iter().map(|line| line.tag.clone()).collect();
+        TagNode::tree(&tags)
+            .into_iter()
+            .filter(|node| node.has_children)
+            .map(|node| {
+                let lines: Vec<&BillingLine> = self
+                    .lines
+                    .iter()
+                    .filter(|line| TagNode::is_within(&line.tag, &node.path))
+                    .collect();
+
+                BillingRollup {
+                    tag: node.path,
+                    depth: node.depth,
+                    time: lines.iter().map(|line| line.time).sum(),
+                    amounts: sum_by_currency(lines.into_iter()),
+                }
+            })
+            .collect()
+    }
+
+    pub fn export(&self, format: ReportFormat) -> String {
+        match format {
+            ReportFormat::Csv => self.to_csv(),
+            ReportFormat::Markdown => self.to_markdown(),
+            ReportFormat::Html => self.to_html(),
+        }
+    }
+
+    /// One row per billed entry.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("Date,Activity,Tag,Hours,Rate,Currency,Amount,Notes\n");
+        for item in &self.items {
+            let row = [
+                format_date(item.date),
+                item.activity.clone(),
+                item.tag.clone(),
+                hours(item.time),
+                format!("{:.2}", item.rate.hourly),
+                item.rate.currency.clone(),
+                format!("{:.2}", item.amount()),
+                item.notes.clone(),
+            ];
+            let row: Vec<String> = row.iter().map(|field| csv_field(field)).collect();
+            csv.push_str(&row.join(","));
+            csv.push('\n');
+        }
+
+        csv
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut md = format!("# Billing report, {} to {}\n\n", self.from, self.to);
+        md.push_str("| Tag | Hours | Rate | Amount |\n| --- | ---: | ---: | ---: |\n");
+        for line in &self.lines {
+            md.push_str(&format!(
+                "| {} | {} | {:.2} {} | {:.2} {} |\n",
+                markdown_cell(&line.tag),
+                hours(line.time),
+                line.rate.hourly,
+                line.rate.currency,
+                line.amount,
+                line.rate.currency
+            ));
+        }
+
+        md.push('\n');
+        for (currency, total) in self.totals() {
+            md.push_str(&format!("**Total: {:.2} {}**\n\n", total, currency));
+        }
+
+        let rollups = self.rollups();
+        if !rollups.is_empty() {
+            md.push_str("## By parent tag\n\n");
+            md.push_str("| Tag | Hours | Amount |\n| --- | ---: | ---: |\n");
+            for rollup in &rollups {
+                md.push_str(&format!(
+                    "| {} | {} | {} |\n",
+                    markdown_cell(&rollup.tag),
+                    hours(rollup.time),
+                    rollup.describe_amounts()
+                ));
+            }
+            md.push('\n');
+        }
+
+        md.push_str("## Details\n\n");
+        md.push_str("| Date | Activity | Tag | Hours | Amount | Notes |\n");
+        md.push_str("| --- | --- | --- | ---: | ---: | --- |\n");
+        for item in &self.items {
+            md.push_str(&format!(
+                "| {} | {} | {} | {} | {:.2} {} | {} |\n",
+                format_date(item.date),
+                markdown_cell(&item.activity),
+                markdown_cell(&item.tag),
+                hours(item.time),
+                item.amount(),
+                item.rate.currency,
+                markdown_cell(&item.notes)
+            ));
+        }
+
+        md
+    }
+
+    pub fn to_html(&self) -> String {
+        let title = format!("Billing report, {} to {}", self.from, self.to);
+        let mut html = format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n</head>\n<body>\n<h1>{}</h1>\n",
+            title, title
+        );
+
+        html.push_str("<table>\n<tr><th>Tag</th><th>Hours</th><th>Rate</th><th>Amount</th></tr>\n");
+        for line in &self.lines {
+            html.push_str(&format!(
+                "<tr><td>{}</td><td>{}</td><td>{:.2} {}</td><td>{:.2} {}</td></tr>\n",
+                html_escape(&line.tag),
+                hours(line.time),
+                line.rate.hourly,
+                html_escape(&line.rate.currency),
+                line.amount,
+                html_escape(&line.rate.currency)
+            ));
+        }
+        html.push_str("</table>\n");
+
+        for (currency, total) in self.totals() {
+            html.push_str(&format!(
+                "<p><strong>Total: {:.2} {}</strong></p>\n",
+                total,
+                html_escape(&currency)
+            ));
+        }
+
+        let rollups = self.rollups();
+        if !rollups.is_empty() {
+            html.push_str("<h2>By parent tag</h2>\n<table>\n");
+            html.push_str("<tr><th>Tag</th><th>Hours</th><th>Amount</th></tr>\n");
+            for rollup in &rollups {
+                html.push_str(&format!(
+                    "<tr><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+                    html_escape(&rollup.tag),
+                    hours(rollup.time),
+                    html_escape(&rollup.describe_amounts())
+                ));
+            }
+            html.push_str("</table>\n");
+        }
+
+        html.push_str("<h2>Details</h2>\n<table>\n");
+        html.push_str("<tr><th>Date</th><th>Activity</th><th>Tag</th><th>Hours</th><th>Amount</th><th>Notes</th></tr>\n");
+        for item in &self.items {
+            html.push_str(&format!(
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{:.2} {}</td><td>{}</td></tr>\n",
+                format_date(item.date),
+                html_escape(&item.activity),
+                html_escape(&item.tag),
+                hours(item.time),
+                item.amount(),
+                html_escape(&item.rate.currency),
+                html_escape(&item.notes).replace('\n', "<br>")
+            ));
+        }
+        html.push_str("</table>\n</body>\n</html>\n");
+
+        html
+    }
+}
+
+/// The time of `entry` within `start..end`. Sessions without a start time count
+/// on the day the entry started, and entries without any only count when
+/// `covers_undated`.
+fn billed_time(
+    entry: &Entry,
+    start: DateTime<Local>,
+    end: DateTime<Local>,
+    covers_undated: bool,
+) -> Duration {
+    match entry.started_at() {
+        Some(started_at) if start <= started_at && started_at < end => {
+            entry.time_between(start, end) + entry.untimed_work_time()
+        }
+        Some(_) => entry.time_between(start, end),
+        None if covers_undated => entry.work_time,
+        None => Duration::ZERO,
+    }
+}
+
+/// Adds up the amounts of `lines`, separately for each currency.
+fn sum_by_currency<'a>(lines: impl Iterator<Item = &'a BillingLine>) -> Vec<(String, f64)> {
+    let mut totals: Vec<(String, f64)> = Vec::new();
+    for line in lines {
+        match totals
+            .iter_mut()
+            .find(|(currency, _)| *currency == line.rate.currency)
+        {
+            Some((_, total)) => *total += line.amount,
+            None => totals.push((line.rate.currency.clone(), line.amount)),
+        }
+    }
+
+    totals
+}
+
+/// `time` in decimal hours, e.g. `"1.25"`.
+pub fn hours(time: Duration) -> String {
+    format!("{:.2}", time.as_secs_f64() / 3600.0)
+}
+
+fn format_date(date: Option<NaiveDate>) -> String {
+    date.map(|date| date.to_string()).unwrap_or_default()
+}
+
+/// Quotes `field` if it would otherwise break the row.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn markdown_cell(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', "<br>")
+}
+
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
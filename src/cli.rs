@@ -0,0 +1,83 @@
+use std::{env, path::PathBuf};
+
+pub const USAGE: &str = "\
+Usage: actt [OPTIONS]
+       actt ctl <COMMAND> [--json]
+
+Options:
+    --data-dir <PATH>   Keep tracking data in PATH. Overrides ACTT_DATA_DIR.
+    --portable          Keep settings and data next to the executable.
+    -h, --help          Print this message.
+
+Commands, sent to the running actt over its control socket once it's turned on
+in the settings:
+    ctl status                  Print the current activity and how long it has run.
+    ctl start <NAME> [--tag T]  Start tracking NAME.
+    ctl pause | resume | stop
+    --json                      Print the reply as JSON.";
+
+/// Environment variable that overrides where tracking data is kept.
+pub const DATA_DIR_VAR: &str = "ACTT_DATA_DIR";
+
+/// Options given on the command line.
+#[derive(Default, Clone)]
+pub struct Args {
+    pub data_dir: Option<PathBuf>,
+    pub portable: bool,
+    pub help: bool,
+    /// The words after `ctl`, when actt is used to control the running app.
+    pub control: Option<Vec<String>>,
+}
+
+impl Args {
+    pub fn parse() -> Result<Self, String> {
+        let mut args = Self::default();
+        let mut input = env::args().skip(1);
+
+        while let Some(arg) = input.next() {
+            match arg.as_str() {
+                "--data-dir" => {
+                    let path = input.next().ok_or("--data-dir needs a path")?;
+                    args.data_dir = Some(PathBuf::from(path));
+                }
+                "--portable" => args.portable = true,
+                "-h" | "--help" => args.help = true,
+                "ctl" => {
+                    args.control = Some(input.collect());
+                    break;
+                }
+                _ => match arg.strip_prefix("--data-dir=") {
+                    Some(path) => args.data_dir = Some(PathBuf::from(path)),
+                    None => return Err(format!("Unknown option: {}", arg)),
+                },
+            }
+        }
+
+        Ok(args)
+    }
+
+    /// The folder next to the executable used in portable mode.
+    pub fn portable_dir() -> Option<PathBuf> {
+        let exe = env::current_exe().ok()?;
+        Some(exe.parent()?.join("actt-data"))
+    }
+
+    /// Portable mode is on when asked for on the command line, or when the
+    /// portable folder already exists next to the executable.
+    pub fn is_portable(&self) -> bool {
+        self.portable || Self::portable_dir().is_some_and(|dir| dir.is_dir())
+    }
+
+    /// Where tracking data should be kept, if it was set on the command line or
+    /// through `ACTT_DATA_DIR`, and which of the two set it.
+    pub fn data_dir_override(&self) -> Option<(PathBuf, &'static str)> {
+        if let Some(data_dir) = &self.data_dir {
+            return Some((data_dir.clone(), "--data-dir"));
+        }
+
+        match env::var_os(DATA_DIR_VAR) {
+            Some(data_dir) if !data_dir.is_empty() => Some((data_dir.into(), DATA_DIR_VAR)),
+            _ => None,
+        }
+    }
+}
@@ -7,3 +7,112 @@ pub fn blue_text(text: &str) -> RichText {
 pub fn red_text(text: &str) -> RichText {
     RichText::new(text).color(Color32::LIGHT_RED)
 }
+
+/// Converts an sRGB channel to linear light.
+fn linear(c: u8) -> f32 {
+    let c = c as f32 / 255.0;
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Converts `color` to OKLab, a color space where the distance between two
+/// colors matches how different they look.
+pub fn to_oklab(color: Color32) -> [f32; 3] {
+    let (r, g, b) = (linear(color.r()), linear(color.g()), linear(color.b()));
+
+    let l = (0.412_221_46 * r + 0.536_332_55 * g + 0.051_445_995 * b).cbrt();
+    let m = (0.211_903_5 * r + 0.680_699_5 * g + 0.107_396_96 * b).cbrt();
+    let s = (0.088_302_46 * r + 0.281_718_85 * g + 0.629_978_7 * b).cbrt();
+
+    [
+        0.210_454_26 * l + 0.793_617_8 * m - 0.004_072_047 * s,
+        1.977_998_5 * l - 2.428_592_2 * m + 0.450_593_7 * s,
+        0.025_904_037 * l + 0.782_771_77 * m - 0.808_675_77 * s,
+    ]
+}
+
+/// How different `a` and `b` look, `0.0` being the same color.
+pub fn color_distance(a: Color32, b: Color32) -> f32 {
+    let (a, b) = (to_oklab(a), to_oklab(b));
+    a.iter()
+        .zip(b.iter())
+        .map(|(a, b)| (a - b).powi(2))
+        .sum::<f32>()
+        .sqrt()
+}
+
+/// Converts an OKLab color back to sRGB, `None` if it can't be shown on a screen.
+pub fn from_oklab([l, a, b]: [f32; 3]) -> Option<Color32> {
+    let l_ = (l + 0.396_337_78 * a + 0.215_803_76 * b).powi(3);
+    let m_ = (l - 0.105_561_346 * a - 0.063_854_17 * b).powi(3);
+    let s_ = (l - 0.089_484_18 * a - 1.291_485_5 * b).powi(3);
+
+    let linear = [
+        4.076_741_7 * l_ - 3.307_711_6 * m_ + 0.230_969_94 * s_,
+        -1.268_438 * l_ + 2.609_757_4 * m_ - 0.341_319_38 * s_,
+        -0.004_196_086_3 * l_ - 0.703_418_6 * m_ + 1.707_614_7 * s_,
+    ];
+
+    let mut rgb = [0; 3];
+    for (channel, c) in rgb.iter_mut().zip(linear) {
+        if !(-0.001..=1.001).contains(&c) {
+            return None;
+        }
+
+        let c = c.clamp(0.0, 1.0);
+        let c = if c <= 0.003_130_8 {
+            c * 12.92
+        } else {
+            1.055 * c.powf(1.0 / 2.4) - 0.055
+        };
+        *channel = (c * 255.0).round() as u8;
+    }
+
+    Some(Color32::from_rgb(rgb[0], rgb[1], rgb[2]))
+}
+
+/// How readable `a` is on top of `b`, from `1.0` (not at all) to `21.0`.
+/// This is the contrast ratio from the WCAG.
+pub fn contrast_ratio(a: Color32, b: Color32) -> f32 {
+    let luminance = |color: Color32| {
+        0.2126 * linear(color.r()) + 0.7152 * linear(color.g()) + 0.0722 * linear(color.b())
+    };
+
+    let (a, b) = (luminance(a), luminance(b));
+    (a.max(b) + 0.05) / (a.min(b) + 0.05)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn oklab_round_trips() {
+        for r in (0..=255).step_by(51) {
+            for g in (0..=255).step_by(51) {
+                for b in (0..=255).step_by(51) {
+                    let color = Color32::from_rgb(r, g, b);
+                    assert_eq!(from_oklab(to_oklab(color)), Some(color));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn oklab_of_black_and_white() {
+        let [l, a, b] = to_oklab(Color32::BLACK);
+        assert!(l.abs() < 1e-4 && a.abs() < 1e-4 && b.abs() < 1e-4);
+
+        let [l, a, b] = to_oklab(Color32::WHITE);
+        assert!((l - 1.0).abs() < 1e-3 && a.abs() < 1e-3 && b.abs() < 1e-3);
+    }
+
+    #[test]
+    fn colors_off_screen_are_rejected() {
+        assert_eq!(from_oklab([1.0, 0.4, 0.0]), None);
+        assert_eq!(from_oklab([-0.5, 0.0, 0.0]), None);
+    }
+}
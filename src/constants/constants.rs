@@ -1,4 +1,77 @@
-use egui::Color32;
+use std::time::Duration;
+
+use egui::{Color32, Key};
 
 pub const DEFAULT_TAG_COLOR: Color32 = Color32::TRANSPARENT;
 pub const EMPTY_TAG: &str = "  ";
+/// The title of the window.
+pub const APP_TITLE: &str = "Actt";
+/// How many recent activities are listed on the start screen.
+pub const RECENT_ACTIVITY_LIMIT: usize = 5;
+/// How many recent activities the command palette offers to start.
+pub const PALETTE_RECENT_LIMIT: usize = 20;
+/// How many matches the command palette lists at once.
+pub const PALETTE_MATCH_LIMIT: usize = 12;
+/// The port the scripting API listens on unless the user picks another one.
+pub const API_DEFAULT_PORT: u16 = 7341;
+/// How long the API server waits between checks for new connections.
+pub const API_POLL_INTERVAL: Duration = Duration::from_millis(50);
+/// How long a request to the API may take to arrive, and to be answered.
+pub const API_TIMEOUT: Duration = Duration::from_secs(5);
+/// The largest request the API reads, in bytes.
+pub const API_MAX_REQUEST: u64 = 64 * 1024;
+/// How many API connections are handled at once. Any more are turned away.
+pub const API_MAX_CONNECTIONS: usize = 8;
+/// The name of the control socket in `XDG_RUNTIME_DIR`.
+pub const SOCKET_NAME: &str = "actt.sock";
+/// Keys used to quick start the activities listed on the start screen.
+pub const NUMBER_KEYS: [Key; 9] = [
+    Key::Num1,
+    Key::Num2,
+    Key::Num3,
+    Key::Num4,
+    Key::Num5,
+    Key::Num6,
+    Key::Num7,
+    Key::Num8,
+    Key::Num9,
+];
+/// How much of a tag's time budget can be used before the user is warned about it.
+pub const GOAL_WARNING_THRESHOLD: f32 = 0.9;
+/// The duration used to show what each time format looks like.
+pub const EXAMPLE_DURATION: Duration = Duration::from_secs(3723);
+/// How far apart, in OKLab, the colors of two tags have to be to tell them apart.
+pub const MIN_COLOR_DISTANCE: f32 = 0.08;
+/// How readable a tag's color has to be on the background, see `contrast_ratio`.
+pub const MIN_TAG_CONTRAST: f32 = 3.0;
+/// The Okabe-Ito palette, made to be told apart with any kind of color blindness.
+pub const OKABE_ITO_PALETTE: [Color32; 8] = [
+    Color32::from_rgb(0xE6, 0x9F, 0x00),
+    Color32::from_rgb(0x56, 0xB4, 0xE9),
+    Color32::from_rgb(0x00, 0x9E, 0x73),
+    Color32::from_rgb(0xF0, 0xE4, 0x42),
+    Color32::from_rgb(0x00, 0x72, 0xB2),
+    Color32::from_rgb(0xD5, 0x5E, 0x00),
+    Color32::from_rgb(0xCC, 0x79, 0xA7),
+    Color32::from_rgb(0x00, 0x00, 0x00),
+];
+/// Paul Tol's bright palette, which is also colorblind safe.
+pub const TOL_BRIGHT_PALETTE: [Color32; 7] = [
+    Color32::from_rgb(0x44, 0x77, 0xAA),
+    Color32::from_rgb(0xEE, 0x66, 0x77),
+    Color32::from_rgb(0x22, 0x88, 0x33),
+    Color32::from_rgb(0xCC, 0xBB, 0x44),
+    Color32::from_rgb(0x66, 0xCC, 0xEE),
+    Color32::from_rgb(0xAA, 0x33, 0x77),
+    Color32::from_rgb(0xBB, 0xBB, 0xBB),
+];
+/// The IBM design palette, which is also colorblind safe.
+pub const IBM_PALETTE: [Color32; 5] = [
+    Color32::from_rgb(0x64, 0x8F, 0xFF),
+    Color32::from_rgb(0x78, 0x5E, 0xF0),
+    Color32::from_rgb(0xDC, 0x26, 0x7F),
+    Color32::from_rgb(0xFE, 0x61, 0x00),
+    Color32::from_rgb(0xFF, 0xB0, 0x00),
+];
+/// Splits hierarchical tags like `client/project/task`.
+pub const TAG_SEPARATOR: char = '/';
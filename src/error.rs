@@ -0,0 +1,104 @@
+use std::{fmt, io, path::PathBuf};
+
+/// Everything that can go wrong in actt. None of these should ever crash the app,
+/// they are shown to the user through `App::show_error` instead.
+#[derive(Debug)]
+pub enum Error {
+    /// A file or folder couldn't be read from or written to.
+    Io { path: PathBuf, source: io::Error },
+    /// A file was read but its contents don't make sense.
+    Parse { path: PathBuf, reason: String },
+    /// The data couldn't be turned into something that can be saved.
+    Serialize(String),
+    /// The platform doesn't have a folder for config files.
+    NoConfigDir,
+    /// The folder the executable is in couldn't be found, needed in portable mode.
+    NoExeDir,
+    ReadOnly(PathBuf),
+    EmptyActivityName,
+    TagExists(String),
+    TagNotFound(String),
+    /// There is no entry at this index, usually because the history changed under it.
+    EntryNotFound(usize),
+    InvalidProfileName(String),
+    ProfileExists(String),
+    /// The entry has time in a week whose timesheet was submitted.
+    WeekSubmitted,
+    /// The scripting API couldn't listen on its port.
+    Api {
+        port: u16,
+        source: io::Error,
+    },
+    /// The operating system couldn't give the randomness API tokens are made from.
+    NoRandomness(String),
+    /// The control socket couldn't be made.
+    Socket {
+        path: PathBuf,
+        source: io::Error,
+    },
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    pub fn io(path: impl Into<PathBuf>, source: io::Error) -> Self {
+        Self::Io {
+            path: path.into(),
+            source,
+        }
+    }
+
+    /// Errors that may have cost the user data are shown in a popup, everything
+    /// else goes in the warning banner.
+    pub fn is_serious(&self) -> bool {
+        matches!(self, Self::Io { .. } | Self::Parse { .. } | Self::Serialize(_))
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io { path, source } => write!(f, "Couldn't use {}: {}", path.display(), source),
+            Self::Parse { path, reason } => {
+                write!(f, "{} is damaged and can't be read: {}", path.display(), reason)
+            }
+            Self::Serialize(reason) => write!(f, "Couldn't save your data: {}", reason),
+            Self::NoConfigDir => write!(f, "Couldn't find the config folder."),
+            Self::NoExeDir => write!(f, "Couldn't find the folder actt is in."),
+            Self::ReadOnly(path) => write!(f, "{} is read-only.", path.display()),
+            Self::EmptyActivityName => write!(f, "Activity name cannot be empty!"),
+            Self::TagExists(tag) => {
+                write!(f, "The tag \"{}\" already exists, choose another one.", tag)
+            }
+            Self::TagNotFound(tag) => write!(f, "The tag \"{}\" doesn't exist.", tag),
+            Self::EntryNotFound(_) => write!(f, "That activity doesn't exist anymore."),
+            Self::InvalidProfileName(_) => {
+                write!(f, "Profile names can't be empty or contain '/', '\\' or '.'.")
+            }
+            Self::ProfileExists(profile) => {
+                write!(f, "The profile \"{}\" already exists.", profile)
+            }
+            Self::WeekSubmitted => {
+                write!(f, "That week's timesheet was submitted, so it can't be changed.")
+            }
+            Self::Api { port, source } => {
+                write!(f, "Couldn't start the API on port {}: {}", port, source)
+            }
+            Self::NoRandomness(reason) => write!(f, "Couldn't make an API token: {}", reason),
+            Self::Socket { path, source } => {
+                write!(f, "Couldn't open the control socket {}: {}", path.display(), source)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io { source, .. } | Self::Api { source, .. } | Self::Socket { source, .. } => {
+                Some(source)
+            }
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,182 @@
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// How often the desktop session is asked for the idle time.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Asks the desktop session how long the user has been idle for. Asking can
+/// take a while, so it's done on a background thread that keeps the latest
+/// answer here.
+#[derive(Default)]
+pub struct IdleMonitor {
+    /// The latest answer, shared with the thread. `None` until it's started.
+    idle_time: Option<Arc<Mutex<Option<Duration>>>>,
+}
+
+impl IdleMonitor {
+    /// How long the user has been idle for across the whole desktop. `None` if
+    /// it can't be told, in which case only input to actt itself is watched.
+    pub fn system_idle_time(&mut self) -> Option<Duration> {
+        let idle_time = self.idle_time.get_or_insert_with(start_polling);
+        *idle_time.lock().unwrap()
+    }
+}
+
+/// Starts the thread that asks for the idle time. It stops once the monitor is
+/// dropped, or straight away if the session can't tell.
+fn start_polling() -> Arc<Mutex<Option<Duration>>> {
+    let idle_time = Arc::new(Mutex::new(None));
+    let monitor = Arc::downgrade(&idle_time);
+
+    thread::spawn(move || {
+        let Some(mut source) = IdleSource::detect() else {
+            return;
+        };
+
+        while let Some(idle_time) = monitor.upgrade() {
+            *idle_time.lock().unwrap() = source.idle_time();
+            drop(idle_time);
+            thread::sleep(POLL_INTERVAL);
+        }
+    });
+
+    idle_time
+}
+
+/// Where the idle time comes from, worked out once per session.
+#[cfg(all(target_os = "linux", feature = "system-idle"))]
+enum IdleSource {
+    // The X11 and XScreenSaver function tables are large.
+    X11(Box<X11Idle>),
+    Gnome,
+}
+
+#[cfg(all(target_os = "linux", feature = "system-idle"))]
+impl IdleSource {
+    fn detect() -> Option<Self> {
+        // XWayland only sees input sent to X11 windows, so it can't be trusted there.
+        if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+            gnome_idle_time().map(|_| Self::Gnome)
+        } else {
+            X11Idle::open().map(|x11| Self::X11(Box::new(x11)))
+        }
+    }
+
+    fn idle_time(&mut self) -> Option<Duration> {
+        match self {
+            Self::X11(x11) => x11.idle_time(),
+            Self::Gnome => gnome_idle_time(),
+        }
+    }
+}
+
+#[cfg(not(all(target_os = "linux", feature = "system-idle")))]
+enum IdleSource {}
+
+#[cfg(not(all(target_os = "linux", feature = "system-idle")))]
+impl IdleSource {
+    fn detect() -> Option<Self> {
+        None
+    }
+
+    fn idle_time(&mut self) -> Option<Duration> {
+        match *self {}
+    }
+}
+
+/// A connection to the X server, which is asked for the idle time through the
+/// XScreenSaver extension.
+#[cfg(all(target_os = "linux", feature = "system-idle"))]
+struct X11Idle {
+    xlib: x11_dl::xlib::Xlib,
+    xss: x11_dl::xss::Xss,
+    display: *mut x11_dl::xlib::Display,
+    info: *mut x11_dl::xss::XScreenSaverInfo,
+}
+
+#[cfg(all(target_os = "linux", feature = "system-idle"))]
+impl X11Idle {
+    fn open() -> Option<Self> {
+        let xlib = x11_dl::xlib::Xlib::open().ok()?;
+        let xss = x11_dl::xss::Xss::open().ok()?;
+
+        // SAFETY: the display is checked before use, and closed again if the
+        // info can't be allocated. Otherwise both are freed on drop.
+        unsafe {
+            let display = (xlib.XOpenDisplay)(std::ptr::null());
+            if display.is_null() {
+                return None;
+            }
+
+            let info = (xss.XScreenSaverAllocInfo)();
+            if info.is_null() {
+                (xlib.XCloseDisplay)(display);
+                return None;
+            }
+
+            Some(Self {
+                xlib,
+                xss,
+                display,
+                info,
+            })
+        }
+    }
+
+    fn idle_time(&mut self) -> Option<Duration> {
+        // SAFETY: `display` and `info` stay valid until drop.
+        unsafe {
+            let root = (self.xlib.XDefaultRootWindow)(self.display);
+            let status = (self.xss.XScreenSaverQueryInfo)(self.display, root, self.info);
+            if status == 0 {
+                return None;
+            }
+
+            // `c_ulong` is only 32 bits wide on some targets.
+            #[allow(clippy::unnecessary_cast)]
+            Some(Duration::from_millis((*self.info).idle as u64))
+        }
+    }
+}
+
+#[cfg(all(target_os = "linux", feature = "system-idle"))]
+impl Drop for X11Idle {
+    fn drop(&mut self) {
+        // SAFETY: both were allocated in `open` and aren't used after this.
+        unsafe {
+            (self.xlib.XFree)(self.info.cast());
+            (self.xlib.XCloseDisplay)(self.display);
+        }
+    }
+}
+
+/// Native Wayland sessions don't expose the idle time to regular clients, but
+/// GNOME's compositor does over D-Bus.
+#[cfg(all(target_os = "linux", feature = "system-idle"))]
+fn gnome_idle_time() -> Option<Duration> {
+    let output = std::process::Command::new("gdbus")
+        .args([
+            "call",
+            "--session",
+            "--dest",
+            "org.gnome.Mutter.IdleMonitor",
+            "--object-path",
+            "/org/gnome/Mutter/IdleMonitor/Core",
+            "--method",
+            "org.gnome.Mutter.IdleMonitor.GetIdletime",
+        ])
+        .output()
+        .ok()?;
+
+    // The reply looks like `(uint64 1234,)`.
+    let reply = String::from_utf8(output.stdout).ok()?;
+    let millis = reply
+        .trim()
+        .trim_start_matches("(uint64 ")
+        .trim_end_matches(",)")
+        .parse()
+        .ok()?;
+
+    Some(Duration::from_millis(millis))
+}
@@ -5,9 +5,18 @@
 pub mod app;
 use app::App;
 
+pub mod cli;
+use cli::Args;
+
 pub mod user;
 pub mod screens;
 pub mod constants;
+pub mod idle;
+pub mod error;
+pub mod billing;
+pub mod palette;
+pub mod api;
+pub mod socket;
 
 use eframe;
 
@@ -16,14 +25,30 @@ use eframe;
 fn main() {
     use egui::Vec2;
 
+    let args = match Args::parse() {
+        Ok(args) if args.help => {
+            println!("{}", cli::USAGE);
+            return;
+        }
+        Ok(Args {
+            control: Some(words),
+            ..
+        }) => std::process::exit(socket::run_client(&words)),
+        Ok(args) => args,
+        Err(msg) => {
+            eprintln!("{}\n\n{}", msg, cli::USAGE);
+            std::process::exit(2);
+        }
+    };
+
     let native_options = eframe::NativeOptions {
         min_window_size: Some(Vec2::new(498.0, 394.0)),
         ..Default::default()
     };
 
     eframe::run_native(
-        "Actt",
+        constants::APP_TITLE,
         native_options,
-        Box::new(|cc| Box::new(App::new(cc))),
+        Box::new(|cc| Box::new(App::new(cc, args))),
     );
 }
@@ -0,0 +1,171 @@
+use crate::billing::ReportFormat;
+use crate::user::{Action, Favorite};
+
+/// Something that can be run from the command palette.
+#[derive(Clone)]
+pub enum Command {
+    Action(Action),
+    Start(Favorite),
+    /// Gives the last entry the tag, or asks for one when it's `None`.
+    RetagLast(Option<String>),
+    /// Exports the billing report of the dates on the billing screen.
+    Export(ReportFormat),
+    ToggleTheme,
+}
+
+impl Command {
+    pub fn label(&self) -> String {
+        match self {
+            Self::Action(action) => action.name().to_string(),
+            Self::Start(activity) => format!("Start {}", activity.label()),
+            Self::RetagLast(None) => "Retag the last entry".to_string(),
+            Self::RetagLast(Some(tag)) => format!("Retag the last entry as \"{}\"", tag),
+            Self::Export(format) => format!("Export the billing report as {}", format.name()),
+            Self::ToggleTheme => "Switch between the dark and light theme".to_string(),
+        }
+    }
+
+    /// Reads a command typed out in full, like `start write docs #client/docs`
+    /// or `retag client/docs`.
+    pub fn parse(query: &str) -> Option<Self> {
+        let query = query.trim_start();
+        let (word, rest) = query.split_once(' ')?;
+        let rest = rest.trim();
+        if rest.is_empty() {
+            return None;
+        }
+
+        match word.to_lowercase().as_str() {
+            "start" => {
+                let (name, tag) = rest.split_once('#').unwrap_or((rest, ""));
+                let name = name.trim();
+                if name.is_empty() {
+                    return None;
+                }
+
+                Some(Self::Start(Favorite::new(
+                    name.to_string(),
+                    tag.trim().to_string(),
+                )))
+            }
+            "retag" => Some(Self::RetagLast(Some(rest.to_string()))),
+            _ => None,
+        }
+    }
+}
+
+/// How well `query` matches `text`, if every character of `query` shows up in
+/// `text` in the same order. Higher is better. Case and spaces are ignored.
+pub fn fuzzy_score(query: &str, text: &str) -> Option<i32> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut start = 0;
+    let mut previous = None;
+    for c in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let found = start + text.get(start..)?.iter().position(|t| *t == c)?;
+
+        score += 1;
+        // Runs of characters and the starts of words count for more, skipped
+        // characters for less.
+        if found > 0 && previous == Some(found - 1) {
+            score += 4;
+        }
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 3;
+        }
+        score -= (found - start).min(3) as i32;
+
+        previous = Some(found);
+        start = found + 1;
+    }
+
+    Some(score)
+}
+
+/// The commands matching `query`, the best match first. A command typed out in
+/// full comes before everything else.
+pub fn search(commands: Vec<Command>, query: &str) -> Vec<Command> {
+    let mut matches: Vec<(i32, Command)> = commands
+        .into_iter()
+        .filter_map(|command| Some((fuzzy_score(query, &command.label())?, command)))
+        .collect();
+    matches.sort_by_key(|(score, _)| -score);
+
+    Command::parse(query)
+        .into_iter()
+        .chain(matches.into_iter().map(|(_, command)| command))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn characters_must_show_up_in_order() {
+        assert!(fuzzy_score("sht", "Show the timesheet").is_some());
+        assert!(fuzzy_score("ths", "Show the timesheet").is_some());
+        assert!(fuzzy_score("zz", "Show the timesheet").is_none());
+        assert!(fuzzy_score("teehs", "Show the timesheet").is_none());
+    }
+
+    #[test]
+    fn case_and_spaces_are_ignored() {
+        assert_eq!(
+            fuzzy_score("S T", "show timesheet"),
+            fuzzy_score("st", "show timesheet")
+        );
+        assert_eq!(fuzzy_score("", "anything"), Some(0));
+    }
+
+    #[test]
+    fn runs_and_word_starts_score_higher() {
+        let run = fuzzy_score("tim", "Show the timesheet").unwrap();
+        let scattered = fuzzy_score("tim", "Start a new item").unwrap();
+        assert!(run > scattered);
+
+        let word_start = fuzzy_score("h", "history").unwrap();
+        let middle = fuzzy_score("h", "Show").unwrap();
+        assert!(word_start > middle);
+    }
+
+    #[test]
+    fn parses_start_commands() {
+        let Some(Command::Start(favorite)) = Command::parse("  Start write docs #client/docs ")
+        else {
+            panic!("not a start command");
+        };
+        assert_eq!(favorite.name, "write docs");
+        assert_eq!(favorite.tag, "client/docs");
+
+        let Some(Command::Start(favorite)) = Command::parse("start write docs") else {
+            panic!("not a start command");
+        };
+        assert_eq!(favorite.name, "write docs");
+        assert_eq!(favorite.tag, "");
+    }
+
+    #[test]
+    fn parses_retag_commands() {
+        let Some(Command::RetagLast(Some(tag))) = Command::parse("retag client/docs") else {
+            panic!("not a retag command");
+        };
+        assert_eq!(tag, "client/docs");
+    }
+
+    #[test]
+    fn rejects_incomplete_commands() {
+        assert!(Command::parse("start").is_none());
+        assert!(Command::parse("start   ").is_none());
+        assert!(Command::parse("start #client").is_none());
+        assert!(Command::parse("retag ").is_none());
+        assert!(Command::parse("stop now").is_none());
+    }
+
+    #[test]
+    fn typed_out_commands_come_first() {
+        let commands = vec![Command::ToggleTheme, Command::RetagLast(None)];
+        let found = search(commands, "retag home");
+        assert!(matches!(found[0], Command::RetagLast(Some(_))));
+    }
+}
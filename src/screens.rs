@@ -1,10 +1,18 @@
+use crate::api::new_token;
 use crate::app::App;
-use crate::user::{Config, Entry};
+use crate::billing::{hours, BillingReport, ReportFormat};
+use crate::error::Error;
+use crate::palette::{search, Command};
+use crate::user::{
+    Action, Config, Entry, Favorite, Goal, GoalKind, GoalPeriod, Keymap, Rounding, Shortcut,
+    TagAssignBehavior, TagNode, TagPalette, Theme, TimeFormat, Timesheet,
+};
 
 use crate::constants::*;
 
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
+use chrono::{Days, Weekday};
 use egui::Response;
 use egui::{
     color_picker::{color_picker_color32, Alpha},
@@ -19,6 +27,10 @@ pub enum Screen {
     History,
     Settings,
     Tags,
+    Billing,
+    Timesheet,
+    /// Shown instead of the app when the data folder can't be used.
+    Error,
 }
 
 pub fn horizontal_menu(app: &mut App, ui: &mut Ui) {
@@ -26,12 +38,51 @@ pub fn horizontal_menu(app: &mut App, ui: &mut Ui) {
         ui.selectable_value(&mut app.screen, Screen::Start, "Home");
         ui.selectable_value(&mut app.screen, Screen::History, "History");
         ui.selectable_value(&mut app.screen, Screen::Tags, "Tags");
+        ui.selectable_value(&mut app.screen, Screen::Timesheet, "Timesheet");
+        ui.selectable_value(&mut app.screen, Screen::Billing, "Billing");
         ui.selectable_value(&mut app.screen, Screen::Settings, "Settings");
+
+        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+            profile_menu(app, ui);
+
+            let hover = format!(
+                "Keyboard shortcuts ({})",
+                app.settings.preferences.keymap.label(Action::ShowShortcuts)
+            );
+            if ui.button("⌨").on_hover_text(hover).clicked() {
+                app.show_shortcuts = true;
+            }
+        });
     });
 
     ui.separator();
 }
 
+/// Lets the user switch between profiles, or make a new one.
+fn profile_menu(app: &mut App, ui: &mut Ui) {
+    let title = format!("Profile: {}", app.settings.profile);
+    ui.menu_button(title, |ui| {
+        for profile in app.settings.profiles.clone() {
+            let selected = profile == app.settings.profile;
+            if ui.selectable_label(selected, &profile).clicked() {
+                if !selected {
+                    app.switch_profile(&profile);
+                }
+                ui.close_menu();
+            }
+        }
+
+        ui.separator();
+        ui.horizontal(|ui| {
+            ui.text_edit_singleline(&mut app.new_profile);
+            if ui.button("New profile").clicked() {
+                app.create_profile();
+                ui.close_menu();
+            }
+        });
+    });
+}
+
 pub fn history_screen(app: &mut App, ctx: &egui::Context, _frame: &mut eframe::Frame) {
     egui::CentralPanel::default().show(ctx, |ui| {
         horizontal_menu(app, ui);
@@ -50,6 +101,12 @@ pub fn history_screen(app: &mut App, ctx: &egui::Context, _frame: &mut eframe::F
 
             let mut config = app.read_config_file();
 
+            ui.horizontal(|ui| {
+                ui.label("Search");
+                ui.text_edit_singleline(&mut app.history_search)
+                    .on_hover_text("Names, tags and notes are searched.");
+            });
+
             if config.entry.len() == 0 || app.tag_list().is_empty() {
                 ui.label("It's empty!");
             } else {
@@ -57,6 +114,10 @@ pub fn history_screen(app: &mut App, ctx: &egui::Context, _frame: &mut eframe::F
             }
         });
     });
+
+    if app.notes_entry.is_some() {
+        notes_window(app, ctx);
+    }
 }
 
 fn activity_listing(
@@ -80,41 +141,96 @@ fn activity_listing(
                     tag_list,
                     colors,
                     ..
-                } = config;
+                } = &*config;
 
                 // `index` is the selected item.
                 for (index, entry) in config.entry.iter().enumerate() {
-                    let total_time = total_time[index].as_secs();
+                    if !app.history_search.trim().is_empty()
+                        && !config.entry_matches(entry, &app.history_search)
+                    {
+                        continue;
+                    }
+
+                    let total_time = total_time.get(index).copied().unwrap_or(entry.work_time);
                     let Entry {
                         name,
                         tag_index,
                         color_index,
+                        ..
                     } = entry;
 
                     // Name
                     column[0].vertical_centered_justified(|ui| {
-                        app.assign_name(ui, name, index);
+                        let Some(mut r) = app.assign_name(ui, name, index) else {
+                            return;
+                        };
+
+                        if app.history_row == Some(index) {
+                            let stroke = ui.visuals().selection.stroke;
+                            ui.painter().rect_stroke(r.rect.expand(2.0), 2.0, stroke);
+                            if app.scroll_to_row {
+                                r.scroll_to_me(Some(egui::Align::Center));
+                                app.scroll_to_row = false;
+                            }
+                        }
+
+                        if !entry.notes.trim().is_empty() {
+                            r = r.on_hover_ui(|ui| notes_view(ui, &entry.notes));
+                        }
+
+                        r.context_menu(|ui| {
+                            if ui.button("Edit notes").clicked() {
+                                app.notes_entry = Some(index);
+                                app.edit_notes = entry.notes.clone();
+                                ui.close_menu();
+                            }
+
+                            let mut billable = entry.billable;
+                            if ui.checkbox(&mut billable, "Billable").changed() {
+                                app.set_billable(index, billable);
+                                ui.close_menu();
+                            }
+                        });
                     });
 
                     // Tag
                     column[1].vertical_centered_justified(|ui| {
                         // This is false when the tag is delete, since the tag doesn't exist it
                         // gets an empty string instead.
-                        let current_tag = if let Some(current_tag) = tag_list.get_mut(*tag_index) {
+                        let current_tag = if let Some(current_tag) = tag_list.get(*tag_index) {
                             current_tag.trim()
                         } else {
                             ""
                         };
 
+                        let extra_tags: Vec<String> = entry
+                            .extra_tags
+                            .iter()
+                            .filter_map(|tag_index| tag_list.get(*tag_index))
+                            .map(|tag| tag.trim().to_string())
+                            .collect();
+                        let label = if extra_tags.is_empty() {
+                            current_tag.to_string()
+                        } else {
+                            format!("{} +{}", current_tag, extra_tags.len())
+                        };
+
                         let tag_is_empty = current_tag.is_empty();
                         let text = if tag_is_empty {
                             RichText::new(current_tag.clone()).color(Color32::TRANSPARENT)
                         } else {
-                            RichText::new(current_tag.clone()).color(colors[*color_index])
+                            let color = colors
+                                .get(*color_index)
+                                .copied()
+                                .unwrap_or(ui.visuals().text_color());
+                            RichText::new(label).color(color)
                         };
 
                         let button = Button::new(text).frame(false);
-                        let r = ui.add(button);
+                        let mut r = ui.add(button);
+                        if !extra_tags.is_empty() {
+                            r = r.on_hover_text(format!("Also tagged {}", extra_tags.join(", ")));
+                        }
 
                         r.context_menu(|ui| {
                             let r = ui.button("Switch tag");
@@ -122,6 +238,9 @@ fn activity_listing(
                             if r.clicked() {
                                 app.show_change_tag_win = true;
                                 app.target_tag_index = index;
+                                app.new_tag = current_tag.to_string();
+                                app.new_tag_color = None;
+                                app.retag_extra_tags = extra_tags.clone();
                                 ui.close_menu();
                             }
                         });
@@ -136,22 +255,41 @@ fn activity_listing(
                     });
 
                     // Time
-                    let minutes = total_time / 60;
-                    let seconds = total_time % 60;
-                    let hours = minutes / 60;
-                    let minutes = minutes % 60;
+                    let time_btn = Button::new(app.format_time(total_time)).frame(false);
+                    column[2].vertical_centered_justified(|ui| {
+                        let mut r = ui.add(time_btn);
+                        let session_count = entry.session_count();
+                        if session_count > 1 {
+                            r = r.on_hover_text(format!("Tracked over {} sessions", session_count));
+                        }
 
-                    let total_time = format!("{}h {}m {}s", hours, minutes, seconds);
+                        if !entry.pauses.is_empty() {
+                            r = r.on_hover_text(format!(
+                                "Paused {} time(s) for {}",
+                                entry.pauses.len(),
+                                app.format_time(entry.pause_time)
+                            ));
+                        }
+
+                        r = r.on_hover_text("Right-click to resume this activity");
+
+                        r.context_menu(|ui| {
+                            if ui.button("Resume this").clicked() {
+                                app.resume_entry(index, true);
+                                ui.close_menu();
+                            }
 
-                    let time_btn = Button::new(total_time).frame(false);
-                    column[2].vertical_centered_justified(|ui| ui.add(time_btn));
+                            if ui.button("Resume as a new entry").clicked() {
+                                app.resume_entry(index, false);
+                                ui.close_menu();
+                            }
+                        });
+                    });
 
                     // Delete
                     column[3].vertical_centered_justified(|ui| {
                         if ui.button("X").clicked() {
-                            app.config.entry.remove(index);
-                            app.config.total_time.remove(index);
-                            app.write_config_file();
+                            app.delete_entry(index);
                         }
                     });
                 }
@@ -169,7 +307,10 @@ pub fn start_screen(app: &mut App, ctx: &egui::Context, _frame: &mut eframe::Fra
     // Which means that the return type needs to cover that as well.
     egui::CentralPanel::default().show(ctx, |ui| {
         app.config = app.read_config_file();
-        let list_of_colors = app.config.colors.clone();
+
+        if app.warning.is_none() {
+            app.warning = (0..app.config.tag_list.len()).find_map(|index| app.goal_warning(index));
+        }
 
         horizontal_menu(app, ui);
         ui.with_layout(egui::Layout::top_down(egui::Align::Center), |ui| {
@@ -190,8 +331,13 @@ pub fn start_screen(app: &mut App, ctx: &egui::Context, _frame: &mut eframe::Fra
                     ui.columns(2, |column| {
                         column[0].vertical_centered_justified(|ui| ui.label("Activity"));
                         column[1].vertical_centered_justified(|ui| {
-                            ui.text_edit_singleline(&mut app.activity_name)
-                                .on_hover_text("What do you want to track?")
+                            let r = app
+                                .activity_combo_box(ui)
+                                .on_hover_text("What do you want to track?");
+                            if app.focus_activity {
+                                r.request_focus();
+                                app.focus_activity = false;
+                            }
                         });
                     });
 
@@ -210,52 +356,185 @@ pub fn start_screen(app: &mut App, ctx: &egui::Context, _frame: &mut eframe::Fra
                         });
                     });
 
+                    ui.columns(2, |column| {
+                        column[0].vertical_centered_justified(|ui| ui.label("Other tags"));
+                        column[1].vertical_centered_justified(|ui| {
+                            let tag_list = app.tag_list();
+                            extra_tags_editor(
+                                ui,
+                                &tag_list,
+                                "extra tags",
+                                &mut app.extra_tags,
+                                &mut app.new_extra_tag,
+                            );
+                        });
+                    });
+
                     ui.columns(2, |column| {
                         column[0].vertical_centered_justified(|ui| ui.label("Tag color"));
                         column[1].vertical_centered_justified(|ui| {
-                            color_picker_color32(ui, &mut app.color, Alpha::Opaque);
+                            if color_picker_color32(ui, &mut app.color, Alpha::Opaque) {
+                                app.color_chosen = true;
+                            }
+                        });
+                    });
+
+                    ui.columns(2, |column| {
+                        column[0].vertical_centered_justified(|ui| ui.label("Planned time"));
+                        column[1].vertical_centered_justified(|ui| {
+                            let planned = egui::DragValue::new(&mut app.planned_minutes)
+                                .clamp_range(0..=1440)
+                                .suffix(" min");
+                            ui.add(planned).on_hover_text(
+                                "How long do you want to spend on it? 0 for no limit.",
+                            );
                         });
                     });
+
+                    if app.planned_minutes > 0 {
+                        ui.checkbox(&mut app.auto_stop, "Stop when the time is up");
+                    }
                 },
             );
 
             ui.label("\n");
             if ui.button("Start").clicked() {
-                if app.activity_name.len() <= 0 {
-                    app.warning = Some("Activity name cannot be empty!".to_string());
-                } else if app.config.preferences.tag_assign_behavior == "picker" {
-                    app.warning = Some(
-                        "Please pick a different color, that one has already been chosen."
-                            .to_string(),
-                    );
-                } else {
-                    let tag_index = app.config.find_tag(&app.tag_list(), &app.tag_name);
-                    let tag_exist = tag_index.is_some();
+                start_from_home(app);
+            }
 
-                    if !tag_exist {
-                        app.color = app.config.colors[tag_index.unwrap()];
-                    }
+            goal_progress_list(app, ui);
+            quick_start_list(app, ctx, ui);
+        });
+    });
+}
 
-                    let does_color_exist = app.config.does_color_exist(&list_of_colors, &app.color);
+/// Checks the activity filled in on the start screen before starting it.
+pub fn start_from_home(app: &mut App) {
+    if app.activity_name.trim().is_empty() {
+        app.show_error(Error::EmptyActivityName);
+    } else if app.settings.preferences.tag_assign_behavior == TagAssignBehavior::Picker
+        && app
+            .config
+            .find_tag(&app.tag_list(), &app.tag_name)
+            .is_none()
+        && !app.config.is_usable_color(
+            &app.config.colors,
+            &app.color,
+            app.settings.preferences.theme.background(),
+        )
+    {
+        app.open_color_picker(None, app.color);
+    } else {
+        start_new_activity(app);
+    }
+}
 
-                    if tag_exist && does_color_exist {
-                        app.color = app.config.random_color(&list_of_colors, &app.color, None);
-                    }
+/// Starts the activity filled in on the start screen.
+fn start_new_activity(app: &mut App) {
+    // Existing tags keep their color, new ones can't take a color that is
+    // already used.
+    let chosen = std::mem::take(&mut app.color_chosen).then_some(app.color);
+    let existed = app
+        .config
+        .find_tag(&app.tag_list(), &app.tag_name)
+        .is_some();
+    app.color = app.tag_color(&app.tag_name);
+    app.warning = match chosen {
+        Some(chosen) if !app.tag_name.trim().is_empty() => {
+            App::tag_color_warning(&app.tag_name, existed, chosen, app.color)
+        }
+        _ => None,
+    };
 
-                    if app.tag_name.is_empty() {
-                        app.tag_name = EMPTY_TAG.to_string();
-                    }
+    if app.tag_name.is_empty() {
+        app.tag_name = EMPTY_TAG.to_string();
+    }
 
-                    app.warning = None;
-                    app.screen = Screen::Tracking;
-                    app.total_time = Some(Instant::now());
-                }
+    app.reset_activity_details();
+    app.start_activity(Instant::now());
+    app.apply_plan();
+}
+
+/// Lists the user's favorite and most recent activities on the start screen.
+/// Each one can be started by clicking it, or by pressing the number next to it.
+fn quick_start_list(app: &mut App, ctx: &egui::Context, ui: &mut Ui) {
+    let favorites = app.config.favorites.clone();
+    let recent: Vec<Favorite> = app
+        .config
+        .recent_activities(RECENT_ACTIVITY_LIMIT + favorites.len())
+        .into_iter()
+        .filter(|activity| !app.config.is_favorite(activity))
+        .take(RECENT_ACTIVITY_LIMIT)
+        .collect();
+
+    if favorites.is_empty() && recent.is_empty() {
+        return;
+    }
+
+    // Number keys shouldn't start anything while the user is typing, or when
+    // they are part of a shortcut.
+    let pressed = if ctx.wants_keyboard_input() || !ctx.input().modifiers.is_none() {
+        None
+    } else {
+        NUMBER_KEYS
+            .iter()
+            .position(|key| ctx.input().key_pressed(*key))
+    };
+
+    let mut to_start = None;
+    let mut to_toggle = None;
+    let activities = favorites
+        .iter()
+        .map(|activity| (activity, true))
+        .chain(recent.iter().map(|activity| (activity, false)));
+
+    ui.label("\n");
+    ui.separator();
+    for (number, (activity, is_favorite)) in activities.enumerate() {
+        if number == 0 && is_favorite {
+            ui.label(blue_text("Favorites"));
+        } else if number == favorites.len() {
+            ui.label(blue_text("Recent"));
+        }
+
+        ui.horizontal(|ui| {
+            let star = if is_favorite { "★" } else { "☆" };
+            let hover = if is_favorite {
+                "Unpin"
+            } else {
+                "Pin to favorites"
+            };
+            if ui.button(star).on_hover_text(hover).clicked() {
+                to_toggle = Some(activity.clone());
+            }
+
+            let text = match NUMBER_KEYS.get(number) {
+                Some(_) => format!("{}. {}", number + 1, activity.label()),
+                None => activity.label(),
+            };
+
+            if ui.button(text).clicked() || pressed == Some(number) {
+                to_start = Some(activity.clone());
             }
         });
-    });
+    }
+
+    if let Some(activity) = to_toggle {
+        app.toggle_favorite(activity);
+    }
+
+    if let Some(activity) = to_start {
+        app.quick_start(activity);
+    }
 }
 
 pub fn tracking_screen(app: &mut App, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+    if let Some(tag_index) = app.config.find_tag(&app.config.tag_list, &app.tag_name) {
+        if let Some(msg) = app.goal_warning(tag_index) {
+            app.warning = Some(msg);
+        }
+    }
+
     egui::CentralPanel::default().show(ctx, |ui| {
         ui.with_layout(egui::Layout::top_down(egui::Align::Center), |ui| {
             ui.label("\n\n\n");
@@ -271,30 +550,52 @@ pub fn tracking_screen(app: &mut App, ctx: &egui::Context, _frame: &mut eframe::
                 }
             };
 
-            let total_time: u64 = match app.pause_time {
-                Some(pause_time) => pause_time.duration_since(app.total_time.unwrap()).as_secs(),
-                None => app.total_time.unwrap().elapsed().as_secs(),
-            };
-
-            let m = total_time / 60;
-            let s = total_time % 60;
-            let h = m / 60;
-            let m = m % 60;
+            app.update_work_time();
+            app.update_idle(ctx);
+            app.update_pomodoro();
+            app.check_plan();
+            ctx.request_repaint();
 
-            let header = format!("{}h {}m {}s", h, m, s);
             match app.screen {
                 Screen::Pause => {
                     ui.heading("Paused");
                 }
                 _ => {
-                    ctx.request_repaint();
-                    ui.heading(header);
+                    ui.heading(app.format_time(app.work_time));
                 }
             }
 
+            let paused_time = app.paused_time();
+            if !paused_time.is_zero() {
+                ui.label(format!("Paused for {}", app.format_time(paused_time)));
+            }
+
+            if let Some(planned_time) = app.planned_time {
+                countdown(app, ctx, ui, planned_time);
+            }
+
+            ui.label("\n");
+            if ui.checkbox(&mut app.pomodoro, "Pomodoro").changed() && app.pomodoro {
+                app.start_pomodoro_work();
+            }
+
+            if app.pomodoro {
+                pomodoro_progress(app, ui);
+            }
+
+            ui.label("\n");
+            ui.checkbox(&mut app.billable, "Billable");
+            ui.collapsing("Notes", |ui| {
+                ui.add(
+                    egui::TextEdit::multiline(&mut app.notes)
+                        .hint_text("# Heading\n- List item")
+                        .desired_rows(4),
+                );
+            });
+
             ui.label("\n");
 
-            ui.columns(2, |columns| {
+            ui.columns(3, |columns| {
                 if columns[0].button("Stop").clicked() {
                     app.add_entry();
                 }
@@ -302,27 +603,230 @@ pub fn tracking_screen(app: &mut App, ctx: &egui::Context, _frame: &mut eframe::
                 match app.screen {
                     Screen::Pause => {
                         if columns[1].button("Resume").clicked() {
-                            app.screen = Screen::Tracking;
-                            // Total run time of a task.
-                            app.total_time =
-                                Some(app.total_time.unwrap() + app.pause_time.unwrap().elapsed());
-                            app.pause_time = None;
+                            app.resume();
                         }
                     }
                     _ => {
                         if columns[1].button("Pause").clicked() {
-                            app.screen = Screen::Pause;
-                            match app.pause_time {
-                                // Some means paused before, don't want that. Reset the value.
-                                Some(_) => app.pause_time = None,
-                                // None means first pause.
-                                None => app.pause_time = Some(Instant::now()),
-                            };
+                            app.pause();
                         }
                     }
                 }
+
+                if columns[2].button("Switch to…").clicked() {
+                    app.show_switch_win = true;
+                }
+            });
+        });
+    });
+
+    if app.show_switch_win {
+        switch_activity_window(app, ctx, _frame);
+    }
+
+    if app.pomodoro_alert.is_some() {
+        pomodoro_alert_window(app, ctx);
+    }
+
+    if app.idle_interval.is_some() {
+        idle_window(app, ctx);
+    }
+
+    if app.short_entry_end.is_some() {
+        short_entry_window(app, ctx);
+    }
+}
+
+/// Asks whether an entry shorter than a minute should be saved.
+fn short_entry_window(app: &mut App, ctx: &egui::Context) {
+    egui::Window::new("")
+        .title_bar(false)
+        .collapsible(false)
+        .show(ctx, |ui| {
+            ui.label(format!(
+                "\"{}\" only lasted {}. Do you want to keep it?",
+                app.activity_name,
+                app.format_time(app.work_time)
+            ));
+
+            ui.horizontal(|ui| {
+                if ui.button("Save").clicked() {
+                    if let Some(end) = app.short_entry_end.take() {
+                        app.add_entry_at(end);
+                    }
+                }
+
+                if ui.button(red_text("Discard")).clicked() {
+                    app.short_entry_end = None;
+                    app.discard_entry();
+                }
+
+                if ui.button("Keep tracking").clicked() {
+                    app.short_entry_end = None;
+                }
+            });
+        });
+}
+
+/// The popup that appears when the user comes back after being idle, asking
+/// what should happen to the time they were away.
+fn idle_window(app: &mut App, ctx: &egui::Context) {
+    let idle_time = match app.idle_interval {
+        Some((start, end)) => end.saturating_duration_since(start),
+        None => return,
+    };
+
+    egui::Window::new("")
+        .title_bar(false)
+        .anchor(egui::Align2::CENTER_CENTER, Vec2::ZERO)
+        .show(ctx, |ui| {
+            ui.heading("Welcome back!");
+            ui.label(format!(
+                "You were idle for {}. What should happen to that time?",
+                app.format_time(idle_time)
+            ));
+            ui.separator();
+
+            ui.horizontal(|ui| {
+                if ui.button("Keep it").clicked() {
+                    app.keep_idle_time();
+                }
+
+                if ui.button(red_text("Discard it")).clicked() {
+                    app.discard_idle_time();
+                }
+            });
+
+            ui.separator();
+            ui.label("Or log it as another activity");
+            ui.horizontal(|ui| {
+                ui.label("Activity");
+                ui.text_edit_singleline(&mut app.idle_activity_name);
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("Tag");
+                let tag_list = app.tag_list();
+                App::tag_combo_box(ui, &tag_list, "idle tags", &mut app.idle_tag_name);
+            });
+
+            let can_reassign = !app.idle_activity_name.trim().is_empty();
+            let reassign_btn = ui
+                .add_enabled(can_reassign, Button::new("Log separately"))
+                .on_disabled_hover_text("Activity name cannot be empty!");
+            if reassign_btn.clicked() {
+                app.reassign_idle_time();
+            }
+        });
+}
+
+/// Shows how much of the planned time is left, or how far over it the user is.
+fn countdown(app: &mut App, ctx: &egui::Context, ui: &mut Ui, planned_time: Duration) {
+    if app.work_time <= planned_time {
+        ui.label(format!(
+            "{} left",
+            app.format_time(planned_time - app.work_time)
+        ));
+    } else {
+        // Flashes to get the user's attention.
+        let overrun = format!("Over by {}", app.format_time(app.work_time - planned_time));
+        if (ctx.input().time as u64).is_multiple_of(2) {
+            ui.label(red_text(&overrun).strong());
+        } else {
+            ui.label(red_text(&overrun).weak());
+        }
+    }
+
+    let progress = app.work_time.as_secs_f32() / planned_time.as_secs_f32();
+    ui.add(egui::ProgressBar::new(progress.min(1.0)).desired_width(200.0));
+}
+
+/// Shows the current pomodoro phase, how much of it is left and how many
+/// cycles have been completed.
+fn pomodoro_progress(app: &mut App, ui: &mut Ui) {
+    let length = app
+        .pomodoro_phase
+        .length(&app.settings.preferences.pomodoro);
+    let elapsed = app.pomodoro_elapsed().min(length);
+
+    ui.label(format!(
+        "{}: {} left",
+        app.pomodoro_phase.name(),
+        app.format_time(length - elapsed)
+    ));
+
+    let progress = if length.is_zero() {
+        1.0
+    } else {
+        elapsed.as_secs_f32() / length.as_secs_f32()
+    };
+    ui.add(egui::ProgressBar::new(progress).desired_width(200.0));
+    ui.label(format!("Cycles completed: {}", app.pomodoro_cycles));
+}
+
+/// The popup that appears when a pomodoro phase ends. It flashes, along with
+/// the rest of the window, until dismissed.
+fn pomodoro_alert_window(app: &mut App, ctx: &egui::Context) {
+    let msg = app.pomodoro_alert.clone().unwrap_or_default();
+    let flash = (ctx.input().time as u64).is_multiple_of(2);
+    let text = if flash {
+        red_text(&msg).size(24.0)
+    } else {
+        blue_text(&msg).size(24.0)
+    };
+
+    if flash {
+        let layer = egui::LayerId::new(egui::Order::Background, egui::Id::new("pomodoro flash"));
+        ctx.layer_painter(layer).rect_filled(
+            ctx.input().screen_rect(),
+            0.0,
+            Color32::from_rgba_unmultiplied(255, 80, 80, 40),
+        );
+    }
+    ctx.request_repaint_after(Duration::from_millis(500));
+
+    egui::Window::new("")
+        .title_bar(false)
+        .anchor(egui::Align2::CENTER_CENTER, Vec2::ZERO)
+        .show(ctx, |ui| {
+            ui.vertical_centered(|ui| {
+                ui.label(text);
+                if ui.button("Ok").clicked() {
+                    app.pomodoro_alert = None;
+                }
             });
         });
+}
+
+/// The popup that appears when a user wants to move on to another activity
+/// without stopping the current one first.
+fn switch_activity_window(app: &mut App, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+    egui::Window::new("").title_bar(false).show(ctx, |ui| {
+        ui.heading("Switch to another activity");
+        ui.horizontal(|ui| {
+            ui.label("Activity");
+            ui.text_edit_singleline(&mut app.next_activity_name);
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Tag");
+            let tag_list = app.tag_list();
+            App::tag_combo_box(ui, &tag_list, "next tags", &mut app.next_tag_name);
+        });
+
+        ui.horizontal(|ui| {
+            if ui.button(red_text("Cancel")).clicked() {
+                app.show_switch_win = false;
+            }
+
+            let can_switch = !app.next_activity_name.trim().is_empty();
+            let switch_btn = ui
+                .add_enabled(can_switch, Button::new("Switch"))
+                .on_disabled_hover_text("Activity name cannot be empty!");
+            if switch_btn.clicked() {
+                app.switch_activity();
+            }
+        });
     });
 }
 
@@ -330,67 +834,1268 @@ pub fn tags_screen(app: &mut App, ctx: &egui::Context, _frame: &mut eframe::Fram
     egui::CentralPanel::default().show(ctx, |ui| {
         horizontal_menu(app, ui);
 
-        ui.columns(2, |column| {
+        let config = app.read_config_file();
+        ui.columns(4, |column| {
             column[0].vertical_centered_justified(|ui| ui.label(blue_text("Tags")));
-            column[1].vertical_centered_justified(|ui| ui.label(red_text("Delete")));
+            column[1].vertical_centered_justified(|ui| ui.label(blue_text("Time spent")));
+            column[2].vertical_centered_justified(|ui| ui.label(blue_text("Goals")));
+            column[3].vertical_centered_justified(|ui| ui.label(red_text("Delete")));
+
+            for node in TagNode::tree(&app.tag_list()) {
+                if app.is_tag_hidden(&node.path) {
+                    continue;
+                }
+
+                column[0].horizontal(|ui| {
+                    ui.add_space(16.0 * node.depth as f32);
+                    if node.has_children {
+                        let arrow = if app.is_collapsed(&node.path) {
+                            "▶"
+                        } else {
+                            "▼"
+                        };
+                        if ui.add(Button::new(arrow).frame(false)).clicked() {
+                            app.toggle_collapsed(&node.path);
+                        }
+                    }
+
+                    let color = node
+                        .tag_index
+                        .and_then(|tag_index| config.colors.get(tag_index).copied())
+                        .unwrap_or(Color32::LIGHT_BLUE);
+                    ui.label(RichText::new(node.name()).color(color));
+                });
+
+                // Parents include the time of everything below them.
+                let total = config.rollup_time(&node.path);
+                column[1].vertical_centered_justified(|ui| ui.label(app.format_time(total)));
+
+                let Some(tag_index) = node.tag_index else {
+                    column[2].label("");
+                    column[3].label("");
+                    continue;
+                };
+
+                column[2].vertical_centered_justified(|ui| {
+                    goal_progress(app, ui, tag_index);
+                    if ui.small_button("Edit goals").clicked() {
+                        app.goal_tag_index = Some(tag_index);
+                    }
+                });
 
-            for tag in app.tag_list().iter_mut() {
-                column[0].vertical_centered_justified(|ui| ui.label(blue_text(tag)));
                 let del_btn = Button::new(red_text("X"));
-                column[1].vertical_centered_justified(|ui| {
+                column[3].vertical_centered_justified(|ui| {
                     let r = ui.add(del_btn);
                     if r.clicked() {
-                        app.delete_tag(tag.to_string());
+                        if let Some(tag) = app.tag_list().get(tag_index) {
+                            app.delete_tag(tag.to_string());
+                        }
                     }
                     r
                 });
             }
         });
     });
+
+    if let Some(tag_index) = app.goal_tag_index {
+        goals_window(app, ctx, tag_index);
+    }
 }
 
-pub fn settings_screen(app: &mut App, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+/// A week of tracked time, with a row per activity and a column per day. Cells
+/// can be edited until the week is submitted.
+pub fn timesheet_screen(app: &mut App, ctx: &egui::Context, _frame: &mut eframe::Frame) {
     egui::CentralPanel::default().show(ctx, |ui| {
         horizontal_menu(app, ui);
-        ui.heading("Settings");
-    });
-}
+        ui.with_layout(egui::Layout::top_down(egui::Align::Center), |ui| {
+            ui.heading("Timesheet");
+            match &app.warning {
+                None => ui.label("\r"),
+                Some(msg) => ui.label(format!("\n{}\n", msg)),
+            };
+        });
+
+        let config = app.read_config_file();
+        let week_start = app.timesheet_week();
+        let submitted = config.is_week_submitted(week_start);
+        let timesheet = Timesheet::new(&config, week_start, &app.timesheet_rows);
 
-pub fn create_tag_window(app: &mut App, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-    egui::Window::new("").title_bar(false).show(ctx, |ui| {
-        ui.heading("Create a new tag");
         ui.horizontal(|ui| {
-            ui.label("Tag name");
-            ui.text_edit_singleline(&mut app.new_tag);
+            if ui.button("◀").on_hover_text("Previous week").clicked() {
+                app.timesheet_week = Some(week_start - Days::new(7));
+            }
+            ui.label(format!("Week of {}", week_start));
+            if ui.button("▶").on_hover_text("Next week").clicked() {
+                app.timesheet_week = Some(week_start + Days::new(7));
+            }
+            if ui.button("This week").clicked() {
+                app.timesheet_week = None;
+            }
+
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                if submitted {
+                    if ui.button("Reopen").clicked() {
+                        app.set_week_submitted(false);
+                    }
+                    ui.label(blue_text("Submitted"));
+                } else {
+                    let r = ui
+                        .button("Submit week")
+                        .on_hover_text("Locks the week so it can't be changed by accident.");
+                    if r.clicked() {
+                        app.set_week_submitted(true);
+                    }
+                }
+            });
         });
+        ui.separator();
 
-        if ui.button(red_text("Cancel")).clicked() {
-            app.show_create_tag_win = false;
-        }
+        let mut edit = None;
+        ScrollArea::both().auto_shrink([false; 2]).show(ui, |ui| {
+            egui::Grid::new("timesheet").striped(true).show(ui, |ui| {
+                ui.label(blue_text("Activity"));
+                for day in 0..7 {
+                    let day = timesheet.day(day).format("%a %d").to_string();
+                    ui.label(blue_text(&day));
+                }
+                ui.label(blue_text("Total"));
+                ui.end_row();
 
-        if ui.button("Done").clicked() {
-            app.show_create_tag_win = false;
+                for (row_index, row) in timesheet.rows.iter().enumerate() {
+                    let tag = config
+                        .tag_list
+                        .get(row.tag_index)
+                        .map(|tag| tag.trim())
+                        .unwrap_or_default();
+                    let label = if tag.is_empty() {
+                        row.name.clone()
+                    } else {
+                        format!("{} ({})", row.name, tag)
+                    };
+                    let color = config
+                        .colors
+                        .get(row.color_index)
+                        .copied()
+                        .unwrap_or(ui.visuals().text_color());
+                    ui.label(RichText::new(label).color(color));
+
+                    for (day, time) in row.days.iter().enumerate() {
+                        let mut hours = time.as_secs_f64() / 3600.0;
+                        if submitted {
+                            ui.label(format!("{:.2}", hours));
+                            continue;
+                        }
+
+                        let cell = egui::DragValue::new(&mut hours)
+                            .clamp_range(0.0..=24.0)
+                            .speed(0.05)
+                            .max_decimals(2);
+                        if ui.add(cell).changed() {
+                            edit = Some((row_index, day, hours));
+                        }
+                    }
+
+                    ui.label(format!("{:.2}", row.total().as_secs_f64() / 3600.0));
+                    ui.end_row();
+                }
+
+                ui.label(RichText::new("Total").strong());
+                for day in 0..7 {
+                    let hours = timesheet.day_total(day).as_secs_f64() / 3600.0;
+                    ui.label(RichText::new(format!("{:.2}", hours)).strong());
+                }
+                let hours = timesheet.total().as_secs_f64() / 3600.0;
+                ui.label(RichText::new(format!("{:.2}", hours)).strong());
+                ui.end_row();
+            });
+
+            if submitted {
+                return;
+            }
+
+            ui.label("\n");
+            ui.horizontal(|ui| {
+                ui.label("Activity");
+                ui.text_edit_singleline(&mut app.timesheet_activity);
+                ui.label("Tag");
+                let tag_list = app.tag_list();
+                App::tag_combo_box(ui, &tag_list, "timesheet tag", &mut app.timesheet_tag);
+                if ui.button("Add row").clicked() {
+                    app.add_timesheet_row();
+                }
+            });
+        });
+
+        if let Some((row_index, day, hours)) = edit {
+            let time = Duration::from_secs_f64(hours * 3600.0);
+            let row = &timesheet.rows[row_index];
+            app.set_timesheet_time(row, timesheet.day(day), time);
         }
     });
 }
 
-/// The popup that appears when a user clicks on "change tag"
-fn change_tag_popup(app: &mut App, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-    egui::Window::new("").title_bar(false).show(ctx, |ui| {
-        ui.horizontal(|ui| {
-            app.tag_list_combo_box(ui);
+/// Rates per tag, and what is billed for them between two dates.
+pub fn billing_screen(app: &mut App, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+    egui::CentralPanel::default().show(ctx, |ui| {
+        horizontal_menu(app, ui);
+        ui.with_layout(egui::Layout::top_down(egui::Align::Center), |ui| {
+            ui.heading("Billing");
+            match &app.warning {
+                None => ui.label("\r"),
+                Some(msg) => ui.label(format!("\n{}\n", msg)),
+            };
         });
 
-        ui.horizontal(|ui| {
-            // Leaving without doing anything
-            if ui.button(red_text("Cancel")).clicked() {
-                app.show_change_tag_win = false;
+        let mut config = app.read_config_file();
+        let mut config_changed = false;
+        let mut settings_changed = false;
+
+        ui.label(blue_text("Rates"));
+        egui::Grid::new("rates").show(ui, |ui| {
+            for node in TagNode::tree(&config.tag_list.clone()) {
+                let Some(tag_index) = node.tag_index else {
+                    continue;
+                };
+
+                ui.horizontal(|ui| {
+                    ui.add_space(16.0 * node.depth as f32);
+                    ui.label(node.name());
+                });
+
+                let mut rate = config.rate(tag_index).cloned();
+                let mut has_rate = rate.is_some();
+                if ui.checkbox(&mut has_rate, "Billed").changed() {
+                    rate = has_rate.then(|| {
+                        // Sub-tags start off with the rate of their parent.
+                        config
+                            .billing_rate(tag_index)
+                            .map(|(_, rate)| rate.clone())
+                            .unwrap_or_default()
+                    });
+                    config_changed = true;
+                }
+
+                if let Some(rate) = rate.as_mut() {
+                    let hourly = egui::DragValue::new(&mut rate.hourly)
+                        .clamp_range(0.0..=100_000.0)
+                        .speed(1.0)
+                        .suffix(" / h");
+                    config_changed |= ui.add(hourly).changed();
+                    let currency =
+                        egui::TextEdit::singleline(&mut rate.currency).desired_width(50.0);
+                    config_changed |= ui.add(currency).changed();
+                } else if let Some((parent, _)) = config.billing_rate(tag_index) {
+                    let parent = config.tag_list[parent].trim().to_string();
+                    ui.label(format!("Billed like {}", parent));
+                    ui.label("");
+                } else {
+                    ui.label("");
+                    ui.label("");
+                }
+                ui.end_row();
+
+                if config_changed && config.rate(tag_index).cloned() != rate {
+                    config.set_rate(tag_index, rate);
+                }
+            }
+        });
+
+        ui.separator();
+        ui.label(blue_text("Report"));
+        let preferences = &mut app.settings.preferences;
+        egui::Grid::new("billing report settings").show(ui, |ui| {
+            ui.label("From");
+            ui.text_edit_singleline(&mut app.billing_from);
+            ui.end_row();
+
+            ui.label("To");
+            ui.text_edit_singleline(&mut app.billing_to);
+            ui.end_row();
+
+            ui.label("Rounding");
+            ui.horizontal(|ui| {
+                egui::ComboBox::from_id_source("billing rounding")
+                    .selected_text(preferences.billing_rounding.name())
+                    .show_ui(ui, |ui| {
+                        for rounding in [Rounding::Up, Rounding::Nearest, Rounding::Down] {
+                            settings_changed |= ui
+                                .selectable_value(
+                                    &mut preferences.billing_rounding,
+                                    rounding,
+                                    rounding.name(),
+                                )
+                                .changed();
+                        }
+                    });
+
+                let increment = egui::DragValue::new(&mut preferences.billing_increment_minutes)
+                    .clamp_range(0..=240)
+                    .prefix("to ")
+                    .suffix(" min");
+                settings_changed |= ui
+                    .add(increment)
+                    .on_hover_text("0 bills the exact time.")
+                    .changed();
+            });
+            ui.end_row();
+        });
+
+        if config_changed {
+            app.config = config.clone();
+            app.write_config_file();
+        }
+
+        if settings_changed {
+            if let Err(e) = app.write_settings_file() {
+                app.show_error(e);
+            }
+        }
+
+        let Some((from, to)) = app.billing_dates() else {
+            ui.label(red_text("Dates have to be written as YYYY-MM-DD."));
+            return;
+        };
+
+        let report = BillingReport::new(&config, &app.settings.preferences, from, to);
+        ui.label("\n");
+        egui::Grid::new("billing report")
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label(blue_text("Tag"));
+                ui.label(blue_text("Hours"));
+                ui.label(blue_text("Rate"));
+                ui.label(blue_text("Amount"));
+                ui.end_row();
+
+                for line in &report.lines {
+                    ui.label(&line.tag);
+                    ui.label(hours(line.time));
+                    ui.label(format!("{:.2} {}", line.rate.hourly, line.rate.currency));
+                    ui.label(format!("{:.2} {}", line.amount, line.rate.currency));
+                    ui.end_row();
+                }
+
+                for (currency, total) in report.totals() {
+                    ui.label(RichText::new("Total").strong());
+                    ui.label("");
+                    ui.label("");
+                    ui.label(RichText::new(format!("{:.2} {}", total, currency)).strong());
+                    ui.end_row();
+                }
+            });
+
+        let rollups = report.rollups();
+        if !rollups.is_empty() {
+            ui.label("\n");
+            ui.label(blue_text("By parent tag"));
+            egui::Grid::new("billing rollups")
+                .striped(true)
+                .show(ui, |ui| {
+                    for rollup in &rollups {
+                        ui.horizontal(|ui| {
+                            ui.add_space(16.0 * rollup.depth as f32);
+                            ui.label(&rollup.tag);
+                        });
+                        ui.label(hours(rollup.time));
+                        ui.label(rollup.describe_amounts());
+                        ui.end_row();
+                    }
+                });
+        }
+
+        if report.items.is_empty() {
+            ui.label("Nothing to bill in these dates.");
+            return;
+        }
+
+        ui.horizontal(|ui| {
+            ui.label("Export as");
+            for format in [
+                ReportFormat::Csv,
+                ReportFormat::Markdown,
+                ReportFormat::Html,
+            ] {
+                if ui.button(format.name()).clicked() {
+                    match app.export_billing(&report, format) {
+                        Ok(path) => app.warning = Some(format!("Saved to {}", path.display())),
+                        Err(e) => app.show_error(e),
+                    }
+                }
+            }
+        });
+    });
+}
+
+/// Progress bars for each goal of the tag at `tag_index`.
+fn goal_progress(app: &App, ui: &mut Ui, tag_index: usize) {
+    for goal in app.config.goals(tag_index) {
+        let used = app.goal_usage(tag_index, goal);
+        let progress = goal.progress(used);
+        let text = format!("{} of {}", app.format_time(used), goal.describe());
+        let text = if goal.kind == GoalKind::AtMost && progress >= GOAL_WARNING_THRESHOLD {
+            red_text(&text)
+        } else {
+            RichText::new(text)
+        };
+
+        ui.add(egui::ProgressBar::new(progress.min(1.0)).text(text));
+    }
+}
+
+/// Lists the progress of every tag's goals on the start screen.
+fn goal_progress_list(app: &mut App, ui: &mut Ui) {
+    let tag_list = app.tag_list();
+    let has_goals = (0..tag_list.len()).any(|index| !app.config.goals(index).is_empty());
+    if !has_goals {
+        return;
+    }
+
+    ui.label("\n");
+    ui.separator();
+    ui.label(blue_text("Goals"));
+    for (tag_index, tag) in tag_list.iter().enumerate() {
+        if app.config.goals(tag_index).is_empty() {
+            continue;
+        }
+
+        ui.label(tag.trim());
+        goal_progress(app, ui, tag_index);
+    }
+}
+
+/// The popup where the goals of the tag at `tag_index` are added, changed and removed.
+fn goals_window(app: &mut App, ctx: &egui::Context, tag_index: usize) {
+    let tag = app.tag_list().get(tag_index).cloned().unwrap_or_default();
+    let mut goals = app.config.goals(tag_index).to_vec();
+    let mut changed = false;
+
+    egui::Window::new("").title_bar(false).show(ctx, |ui| {
+        ui.heading(format!("Goals for {}", tag.trim()));
+
+        let mut to_remove = None;
+        for (index, goal) in goals.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                egui::ComboBox::from_id_source(("goal kind", index))
+                    .selected_text(match goal.kind {
+                        GoalKind::AtLeast => "At least",
+                        GoalKind::AtMost => "At most",
+                    })
+                    .show_ui(ui, |ui| {
+                        changed |= ui
+                            .selectable_value(&mut goal.kind, GoalKind::AtLeast, "At least")
+                            .changed();
+                        changed |= ui
+                            .selectable_value(&mut goal.kind, GoalKind::AtMost, "At most")
+                            .changed();
+                    });
+
+                let hours = egui::DragValue::new(&mut goal.hours)
+                    .clamp_range(0.0..=168.0)
+                    .speed(0.25)
+                    .suffix("h");
+                changed |= ui.add(hours).changed();
+
+                egui::ComboBox::from_id_source(("goal period", index))
+                    .selected_text(match goal.period {
+                        GoalPeriod::Daily => "per day",
+                        GoalPeriod::Weekly => "per week",
+                    })
+                    .show_ui(ui, |ui| {
+                        changed |= ui
+                            .selectable_value(&mut goal.period, GoalPeriod::Daily, "per day")
+                            .changed();
+                        changed |= ui
+                            .selectable_value(&mut goal.period, GoalPeriod::Weekly, "per week")
+                            .changed();
+                    });
+
+                if ui.button(red_text("X")).clicked() {
+                    to_remove = Some(index);
+                }
+            });
+        }
+
+        if let Some(index) = to_remove {
+            goals.remove(index);
+            changed = true;
+        }
+
+        ui.horizontal(|ui| {
+            if ui.button("Add goal").clicked() {
+                goals.push(Goal::default());
+                changed = true;
+            }
+
+            if ui.button("Done").clicked() {
+                app.goal_tag_index = None;
+            }
+        });
+    });
+
+    if changed {
+        *app.config.goals_mut(tag_index) = goals;
+        app.write_config_file();
+    }
+}
+
+pub fn settings_screen(app: &mut App, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+    egui::CentralPanel::default().show(ctx, |ui| {
+        horizontal_menu(app, ui);
+        ui.heading("Settings");
+        if let Some(msg) = &app.warning {
+            ui.label(red_text(msg));
+        }
+
+        let mut preferences = app.settings.preferences.clone();
+        let mut changed = false;
+
+        // The next key pressed becomes the shortcut being recorded. Escape cancels.
+        if let Some(action) = app.recording_shortcut {
+            let pressed = ctx.input().events.iter().find_map(|event| match event {
+                egui::Event::Key {
+                    key,
+                    pressed: true,
+                    modifiers,
+                } => Some((*key, *modifiers)),
+                _ => None,
+            });
+
+            if let Some((key, modifiers)) = pressed {
+                if key != egui::Key::Escape {
+                    let shortcut = Shortcut::from_input(key, modifiers);
+                    preferences.keymap.set(action, Some(shortcut));
+                    changed = true;
+                }
+                app.recording_shortcut = None;
+            }
+        }
+
+        ScrollArea::vertical().show(ui, |ui| {
+            ui.separator();
+            ui.label(blue_text("General"));
+            egui::Grid::new("general settings").show(ui, |ui| {
+                ui.label("Theme");
+                ui.horizontal(|ui| {
+                    changed |= ui
+                        .selectable_value(&mut preferences.theme, Theme::Dark, "Dark")
+                        .changed();
+                    changed |= ui
+                        .selectable_value(&mut preferences.theme, Theme::Light, "Light")
+                        .changed();
+                });
+                ui.end_row();
+
+                ui.label("Time format");
+                egui::ComboBox::from_id_source("time format")
+                    .selected_text(preferences.time_format.format(EXAMPLE_DURATION))
+                    .show_ui(ui, |ui| {
+                        for format in [TimeFormat::Long, TimeFormat::Clock, TimeFormat::Decimal] {
+                            let text = format.format(EXAMPLE_DURATION);
+                            changed |= ui
+                                .selectable_value(&mut preferences.time_format, format, text)
+                                .changed();
+                        }
+                    });
+                ui.end_row();
+
+                ui.label("Week starts on");
+                egui::ComboBox::from_id_source("week start")
+                    .selected_text(preferences.week_start.to_string())
+                    .show_ui(ui, |ui| {
+                        let mut day = Weekday::Mon;
+                        for _ in 0..7 {
+                            changed |= ui
+                                .selectable_value(&mut preferences.week_start, day, day.to_string())
+                                .changed();
+                            day = day.succ();
+                        }
+                    });
+                ui.end_row();
+
+                ui.label("Tag palette");
+                egui::ComboBox::from_id_source("tag palette")
+                    .selected_text(preferences.tag_palette.name())
+                    .show_ui(ui, |ui| {
+                        for palette in [
+                            TagPalette::Generated,
+                            TagPalette::OkabeIto,
+                            TagPalette::TolBright,
+                            TagPalette::Ibm,
+                        ] {
+                            let r = ui.selectable_value(
+                                &mut preferences.tag_palette,
+                                palette,
+                                palette.name(),
+                            );
+                            changed |= r.changed();
+                        }
+                    });
+                ui.end_row();
+
+                ui.label("Tag colors");
+                egui::ComboBox::from_id_source("tag assign behavior")
+                    .selected_text(preferences.tag_assign_behavior.name())
+                    .show_ui(ui, |ui| {
+                        for behavior in [TagAssignBehavior::Random, TagAssignBehavior::Picker] {
+                            changed |= ui
+                                .selectable_value(
+                                    &mut preferences.tag_assign_behavior,
+                                    behavior,
+                                    behavior.name(),
+                                )
+                                .on_hover_text("What to do when a new tag's color is taken.")
+                                .changed();
+                        }
+                    });
+                ui.end_row();
+            });
+
+            ui.separator();
+            ui.label(blue_text("Data"));
+            egui::Grid::new("data settings").show(ui, |ui| {
+                ui.label("Data file");
+                ui.label(app.config_file.display().to_string());
+                ui.end_row();
+
+                ui.label("Data folder");
+                match app.args.data_dir_override() {
+                    Some((_, source)) => {
+                        ui.label(format!("Set by {}", source));
+                    }
+                    None => {
+                        ui.horizontal(|ui| {
+                            ui.text_edit_singleline(&mut app.new_data_dir)
+                                .on_hover_text(
+                                    "Data that is already in the old folder isn't moved.",
+                                );
+
+                            let new_data_dir = app.new_data_dir.trim().to_string();
+                            if ui.button("Use").clicked() && !new_data_dir.is_empty() {
+                                app.change_data_dir(Some(new_data_dir.into()));
+                            }
+
+                            if ui.button("Default").clicked() {
+                                app.change_data_dir(None);
+                            }
+                        });
+                    }
+                }
+                ui.end_row();
+
+                if app.args.is_portable() {
+                    ui.label("Portable mode");
+                    ui.label("Settings and data are kept next to actt.");
+                    ui.end_row();
+                }
+
+                ui.label("Backups to keep");
+                let retention =
+                    egui::DragValue::new(&mut preferences.backup_retention).clamp_range(0..=100);
+                changed |= ui
+                    .add(retention)
+                    .on_hover_text("A backup is made every time actt starts. 0 turns backups off.")
+                    .changed();
+                ui.end_row();
+            });
+
+            ui.separator();
+            ui.label(blue_text("Stopping"));
+            egui::Grid::new("stop settings").show(ui, |ui| {
+                ui.label("Throw away entries shorter than");
+                let min_time = egui::DragValue::new(&mut preferences.min_entry_seconds)
+                    .clamp_range(0..=3600)
+                    .suffix(" s");
+                changed |= ui
+                    .add(min_time)
+                    .on_hover_text("0 keeps every entry.")
+                    .changed();
+                ui.end_row();
+
+                ui.label("Entry rounding");
+                ui.horizontal(|ui| {
+                    egui::ComboBox::from_id_source("entry rounding")
+                        .selected_text(preferences.entry_rounding.name())
+                        .show_ui(ui, |ui| {
+                            for rounding in [Rounding::Up, Rounding::Nearest, Rounding::Down] {
+                                changed |= ui
+                                    .selectable_value(
+                                        &mut preferences.entry_rounding,
+                                        rounding,
+                                        rounding.name(),
+                                    )
+                                    .changed();
+                            }
+                        });
+
+                    let granularity = egui::DragValue::new(&mut preferences.entry_rounding_minutes)
+                        .clamp_range(0..=240)
+                        .prefix("to ")
+                        .suffix(" min");
+                    changed |= ui
+                        .add(granularity)
+                        .on_hover_text("0 keeps the exact time.")
+                        .changed();
+                });
+                ui.end_row();
+
+                ui.label("Short entries");
+                changed |= ui
+                    .checkbox(
+                        &mut preferences.confirm_short_entries,
+                        "Ask before saving entries under a minute",
+                    )
+                    .changed();
+                ui.end_row();
+            });
+
+            ui.separator();
+            ui.label(blue_text("Idle detection"));
+            ui.horizontal(|ui| {
+                ui.label("Ask about idle time after");
+                let threshold = egui::DragValue::new(&mut preferences.idle_threshold_minutes)
+                    .clamp_range(0..=240)
+                    .suffix(" min");
+                changed |= ui
+                    .add(threshold)
+                    .on_hover_text("0 turns idle detection off.")
+                    .changed();
+            });
+
+            let pomodoro = &mut preferences.pomodoro;
+            ui.separator();
+            ui.label(blue_text("Pomodoro"));
+            egui::Grid::new("pomodoro settings").show(ui, |ui| {
+                ui.label("Work (minutes)");
+                changed |= ui
+                    .add(egui::DragValue::new(&mut pomodoro.work_minutes).clamp_range(1..=240))
+                    .changed();
+                ui.end_row();
+
+                ui.label("Short break (minutes)");
+                changed |= ui
+                    .add(egui::DragValue::new(&mut pomodoro.break_minutes).clamp_range(1..=120))
+                    .changed();
+                ui.end_row();
+
+                ui.label("Long break (minutes)");
+                let long_break =
+                    egui::DragValue::new(&mut pomodoro.long_break_minutes).clamp_range(1..=120);
+                changed |= ui.add(long_break).changed();
+                ui.end_row();
+
+                ui.label("Long break every");
+                changed |= ui
+                    .add(
+                        egui::DragValue::new(&mut pomodoro.long_break_every)
+                            .clamp_range(1..=12)
+                            .suffix(" cycles"),
+                    )
+                    .changed();
+                ui.end_row();
+
+                ui.label("Sound");
+                changed |= ui
+                    .checkbox(&mut pomodoro.play_sound, "Play a sound when a phase ends")
+                    .changed();
+                ui.end_row();
+            });
+
+            ui.separator();
+            ui.label(blue_text("Keyboard"));
+            egui::Grid::new("keyboard settings").show(ui, |ui| {
+                for action in Action::ALL {
+                    ui.label(action.name());
+                    let text = if app.recording_shortcut == Some(action) {
+                        "Press a key…".to_string()
+                    } else {
+                        preferences.keymap.label(action)
+                    };
+
+                    let r = ui
+                        .button(text)
+                        .on_hover_text("Click, then press the new shortcut. Esc cancels.");
+                    if r.clicked() {
+                        // Otherwise pressing Enter or Space would click the button again.
+                        r.surrender_focus();
+                        app.recording_shortcut = Some(action);
+                    }
+
+                    if ui.small_button("Clear").clicked() {
+                        preferences.keymap.set(action, None);
+                        changed = true;
+                    }
+                    ui.end_row();
+                }
+            });
+
+            if ui.button("Reset shortcuts").clicked() {
+                preferences.keymap = Keymap::default();
+                changed = true;
+            }
+
+            ui.separator();
+            ui.label(blue_text("Scripting API"));
+            egui::Grid::new("api settings").show(ui, |ui| {
+                ui.label("Local API");
+                let r = ui.checkbox(
+                    &mut preferences.api_enabled,
+                    "Let scripts control actt over HTTP",
+                );
+                if r.changed() {
+                    if preferences.api_token.is_empty() {
+                        match new_token() {
+                            Ok(token) => preferences.api_token = token,
+                            Err(e) => {
+                                preferences.api_enabled = false;
+                                app.show_error(e);
+                            }
+                        }
+                    }
+                    changed = true;
+                }
+                ui.end_row();
+
+                ui.label("Port");
+                let mut port = app.api_port_draft.unwrap_or(preferences.api_port);
+                let r = ui.add(egui::DragValue::new(&mut port).clamp_range(1024..=65535));
+                // Every port passed on the way would otherwise be opened.
+                if r.dragged() || r.has_focus() {
+                    app.api_port_draft = Some(port);
+                } else {
+                    app.api_port_draft = None;
+                    if port != preferences.api_port {
+                        preferences.api_port = port;
+                        changed = true;
+                    }
+                }
+                ui.end_row();
+
+                ui.label("Token");
+                ui.horizontal(|ui| {
+                    ui.label(RichText::new(&preferences.api_token).monospace());
+                    if ui.small_button("Copy").clicked() {
+                        ui.output().copied_text = preferences.api_token.clone();
+                    }
+
+                    let r = ui
+                        .small_button("New token")
+                        .on_hover_text("Scripts using the old token will stop working.");
+                    if r.clicked() {
+                        match new_token() {
+                            Ok(token) => {
+                                preferences.api_token = token;
+                                changed = true;
+                            }
+                            Err(e) => app.show_error(e),
+                        }
+                    }
+                });
+                ui.end_row();
+
+                ui.label("Control socket");
+                changed |= ui
+                    .checkbox(
+                        &mut preferences.control_socket,
+                        "Let status bars and shell prompts use `actt ctl`",
+                    )
+                    .changed();
+                ui.end_row();
+            });
+
+            if let Some(socket) = &app.socket {
+                ui.label(format!("Socket at {}", socket.path.display()));
+            }
+
+            if let Some(api) = &app.api {
+                let example = format!(
+                    "curl -H \"Authorization: Bearer {}\" http://127.0.0.1:{}/status",
+                    api.token, api.port
+                );
+                ui.label(format!("Listening on http://127.0.0.1:{}", api.port))
+                    .on_hover_text(example);
+            }
+        });
+
+        if changed {
+            app.settings.preferences = preferences;
+            app.warning = app.write_settings_file().err().map(|e| e.to_string());
+        }
+    });
+}
+
+/// Shown instead of the app when the data folder can't be used, so the user can
+/// pick another one.
+pub fn error_screen(app: &mut App, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+    egui::CentralPanel::default().show(ctx, |ui| {
+        ui.with_layout(egui::Layout::top_down(egui::Align::Center), |ui| {
+            ui.label("\n");
+            ui.heading("Your data can't be opened");
+            ui.label("\n");
+            if let Some(error) = &app.data_error {
+                ui.label(red_text(&error.to_string()));
             }
+            ui.label("\n");
+
+            if let Some((_, source)) = app.args.data_dir_override() {
+                ui.label(format!(
+                    "The data folder is set by {}, change it there and restart actt.",
+                    source
+                ));
+            } else {
+                ui.label("Keep your data in another folder instead:");
+                ui.text_edit_singleline(&mut app.new_data_dir);
+
+                let new_data_dir = app.new_data_dir.trim().to_string();
+                let use_btn =
+                    ui.add_enabled(!new_data_dir.is_empty(), Button::new("Use this folder"));
+                if use_btn.clicked() {
+                    app.change_data_dir(Some(new_data_dir.into()));
+                }
+
+                if ui.button("Use the default folder").clicked() {
+                    app.change_data_dir(None);
+                }
+            }
+
+            ui.label("\n");
+            ui.horizontal(|ui| {
+                if ui.button("Try again").clicked() {
+                    app.open_data_dir();
+                }
+
+                if ui.button(red_text("Quit")).clicked() {
+                    _frame.close();
+                }
+            });
+        });
+    });
+}
+
+/// Shows an error that needs the user's attention until they dismiss it.
+pub fn error_popup(app: &mut App, ctx: &egui::Context) {
+    let Some(error) = &app.error else {
+        return;
+    };
 
-            // Switch tags around
+    let mut dismissed = false;
+    egui::Window::new("Something went wrong")
+        .collapsible(false)
+        .resizable(false)
+        .show(ctx, |ui| {
+            ui.label(red_text(&error.to_string()));
+            ui.label("\n");
             if ui.button("Ok").clicked() {
+                dismissed = true;
+            }
+        });
+
+    if dismissed {
+        app.error = None;
+    }
+}
+
+/// Lists every keyboard shortcut.
+pub fn shortcuts_window(app: &mut App, ctx: &egui::Context) {
+    let keymap = &app.settings.preferences.keymap;
+    let mut open = app.show_shortcuts;
+    egui::Window::new("Keyboard shortcuts")
+        .open(&mut open)
+        .collapsible(false)
+        .resizable(false)
+        .show(ctx, |ui| {
+            egui::Grid::new("shortcuts").striped(true).show(ui, |ui| {
+                for action in Action::ALL {
+                    ui.label(action.name());
+                    ui.label(blue_text(&keymap.label(action)));
+                    ui.end_row();
+                }
+            });
+
+            ui.label("\n");
+            ui.label("Shortcuts can be changed in the settings.");
+        });
+
+    app.show_shortcuts = open;
+}
+
+/// A search box over everything that can be done, so the app can be used
+/// without going through the menus. Enter runs the highlighted match.
+pub fn command_palette(app: &mut App, ctx: &egui::Context) {
+    let matches = search(app.palette_commands(), &app.palette_query);
+    let (up, down, enter, escape) = {
+        let input = ctx.input();
+        (
+            input.key_pressed(egui::Key::ArrowUp),
+            input.key_pressed(egui::Key::ArrowDown),
+            input.key_pressed(egui::Key::Enter),
+            input.key_pressed(egui::Key::Escape),
+        )
+    };
+
+    if escape {
+        app.show_palette = false;
+        return;
+    }
+
+    let shown = matches.len().min(PALETTE_MATCH_LIMIT);
+    if down {
+        app.palette_selected += 1;
+    }
+    if up {
+        app.palette_selected = app.palette_selected.saturating_sub(1);
+    }
+    app.palette_selected = app.palette_selected.min(shown.saturating_sub(1));
+
+    let mut picked = enter.then_some(app.palette_selected);
+    egui::Window::new("Command palette")
+        .title_bar(false)
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_TOP, Vec2::new(0.0, 40.0))
+        .show(ctx, |ui| {
+            let edit = egui::TextEdit::singleline(&mut app.palette_query)
+                .hint_text("Search, or type \"start name #tag\" or \"retag tag\"")
+                .desired_width(400.0);
+            let r = ui.add(edit);
+            r.request_focus();
+            if r.changed() {
+                app.palette_selected = 0;
+            }
+
+            ui.separator();
+            if matches.is_empty() {
+                ui.label("Nothing matches.");
+            }
+
+            let keymap = &app.settings.preferences.keymap;
+            for (index, command) in matches.iter().take(shown).enumerate() {
+                ui.horizontal(|ui| {
+                    let selected = index == app.palette_selected;
+                    if ui.selectable_label(selected, command.label()).clicked() {
+                        picked = Some(index);
+                    }
+
+                    if let Command::Action(action) = command {
+                        if let Some(shortcut) = keymap.shortcut(*action) {
+                            ui.label(RichText::new(shortcut.label()).weak());
+                        }
+                    }
+                });
+            }
+        });
+
+    if let Some(command) = picked.and_then(|index| matches.into_iter().nth(index)) {
+        app.show_palette = false;
+        app.palette_query.clear();
+        app.run_command(command);
+    }
+}
+
+/// Lets the user pick a color for a new tag whose color is too close to another
+/// tag's, with a preview of how it looks next to the existing tags.
+pub fn tag_color_picker_window(app: &mut App, ctx: &egui::Context) {
+    let config = app.read_config_file();
+    let tag_name = match app.picker_entry {
+        Some(_) => app.new_tag.trim().to_string(),
+        None => app.tag_name.trim().to_string(),
+    };
+
+    egui::Window::new("Pick a tag color")
+        .collapsible(false)
+        .resizable(false)
+        .show(ctx, |ui| {
+            ui.label(format!(
+                "\"{}\" needs a color that is easy to tell apart from your other tags.",
+                tag_name
+            ));
+            color_picker_color32(ui, &mut app.picked_color, Alpha::Opaque);
+
+            ui.separator();
+            ui.horizontal_wrapped(|ui| {
+                ui.label(RichText::new(&tag_name).color(app.picked_color).strong());
+                for (tag, color) in config.tag_list.iter().zip(config.colors.iter()) {
+                    if !tag.trim().is_empty() {
+                        ui.label(RichText::new(tag.trim()).color(*color));
+                    }
+                }
+            });
+
+            let too_close = config
+                .closest_color(&app.picked_color)
+                .filter(|(_, distance)| *distance < MIN_COLOR_DISTANCE);
+            if let Some((color_index, _)) = too_close {
+                let msg = match config.tag_list.get(color_index).map(|tag| tag.trim()) {
+                    Some(tag) if !tag.is_empty() => {
+                        format!("This color is too close to the one of \"{}\".", tag)
+                    }
+                    _ => "This color is too close to the one of another tag.".to_string(),
+                };
+                ui.label(red_text(&msg));
+            }
+
+            let background = app.settings.preferences.theme.background();
+            let hard_to_read = contrast_ratio(app.picked_color, background) < MIN_TAG_CONTRAST;
+            if hard_to_read {
+                ui.label(red_text("This color is hard to read on the background."));
+            }
+
+            ui.horizontal(|ui| {
+                if ui.button(red_text("Cancel")).clicked() {
+                    app.show_color_picker = false;
+                }
+
+                if ui
+                    .add_enabled(too_close.is_none() && !hard_to_read, Button::new("Done"))
+                    .clicked()
+                {
+                    app.show_color_picker = false;
+                    match app.picker_entry {
+                        Some(index) => app.create_tag(index, Some(app.picked_color)),
+                        None => {
+                            app.color = app.picked_color;
+                            start_new_activity(app);
+                        }
+                    }
+                }
+            });
+        });
+}
+
+pub fn create_tag_window(app: &mut App, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+    egui::Window::new("").title_bar(false).show(ctx, |ui| {
+        ui.heading("Create a new tag");
+        ui.horizontal(|ui| {
+            ui.label("Tag name");
+            ui.text_edit_singleline(&mut app.new_tag);
+        });
+
+        if ui.button(red_text("Cancel")).clicked() {
+            app.show_create_tag_win = false;
+        }
+
+        if ui.button("Done").clicked() {
+            app.create_tag(app.target_tag_index, None);
+        }
+    });
+}
+
+/// Shows `notes`, with lines starting with `# ` as headings and lines starting
+/// with `- ` or `* ` as list items.
+fn notes_view(ui: &mut Ui, notes: &str) {
+    for line in notes.lines() {
+        if let Some(heading) = line.strip_prefix("# ") {
+            ui.label(RichText::new(heading).strong());
+        } else if let Some(item) = line.strip_prefix("- ").or_else(|| line.strip_prefix("* ")) {
+            ui.label(format!("• {}", item));
+        } else {
+            ui.label(line);
+        }
+    }
+}
+
+/// The popup where the notes of an entry are edited from the history screen.
+fn notes_window(app: &mut App, ctx: &egui::Context) {
+    egui::Window::new("Notes")
+        .collapsible(false)
+        .show(ctx, |ui| {
+            ui.add(egui::TextEdit::multiline(&mut app.edit_notes).desired_rows(8));
+
+            ui.horizontal(|ui| {
+                if ui.button(red_text("Cancel")).clicked() {
+                    app.notes_entry = None;
+                }
+
+                if ui.button("Save").clicked() {
+                    app.save_notes();
+                }
+            });
+        });
+}
+
+/// Lets the user add and remove the tags of an activity besides its primary one.
+/// `new_tag` holds the tag being typed in.
+fn extra_tags_editor(
+    ui: &mut Ui,
+    tag_list: &[String],
+    id: &str,
+    extra_tags: &mut Vec<String>,
+    new_tag: &mut String,
+) {
+    let mut to_remove = None;
+    ui.horizontal_wrapped(|ui| {
+        for (index, tag) in extra_tags.iter().enumerate() {
+            let r = ui.small_button(format!("{} ×", tag.trim()));
+            if r.on_hover_text("Remove this tag").clicked() {
+                to_remove = Some(index);
+            }
+        }
+    });
+
+    if let Some(index) = to_remove {
+        extra_tags.remove(index);
+    }
+
+    ui.horizontal(|ui| {
+        App::tag_combo_box(ui, tag_list, id, new_tag);
+
+        let tag = new_tag.trim().to_string();
+        let is_new = !tag.is_empty()
+            && !extra_tags
+                .iter()
+                .any(|extra| Config::normalize_tag(extra) == Config::normalize_tag(&tag));
+        if ui.add_enabled(is_new, Button::new("Add")).clicked() {
+            extra_tags.push(tag);
+            new_tag.clear();
+        }
+    });
+}
+
+/// The popup that appears when a user clicks on "change tag"
+fn change_tag_popup(app: &mut App, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+    egui::Window::new("").title_bar(false).show(ctx, |ui| {
+        let tag_list = app.tag_list();
+        ui.horizontal(|ui| {
+            ui.label("Tag");
+            App::tag_combo_box(ui, &tag_list, "change tag", &mut app.new_tag);
+
+            // Shows the color the tag will get unless the user picks another.
+            let mut color = match app.new_tag_color {
+                Some(color) => color,
+                None => app.tag_color(&app.new_tag),
+            };
+            if ui.color_edit_button_srgba(&mut color).changed() {
+                app.new_tag_color = Some(color);
+            }
+        });
+
+        ui.label("Other tags");
+        extra_tags_editor(
+            ui,
+            &tag_list,
+            "change extra tags",
+            &mut app.retag_extra_tags,
+            &mut app.new_extra_tag,
+        );
+
+        ui.horizontal(|ui| {
+            // Leaving without doing anything
+            if ui.button(red_text("Cancel")).clicked() {
+                app.new_tag.clear();
+                app.new_tag_color = None;
                 app.show_change_tag_win = false;
             }
+
+            // Switch tags around, an existing tag is reused and a new one is made.
+            // The main tag goes first so the other tags are checked against it.
+            if ui.button("Ok").clicked() {
+                let extra_tags = std::mem::take(&mut app.retag_extra_tags);
+                if app.new_tag.trim().is_empty() {
+                    app.show_change_tag_win = false;
+                } else {
+                    app.create_tag(app.target_tag_index, app.new_tag_color);
+                }
+                app.set_extra_tags(app.target_tag_index, &extra_tags);
+            }
         });
     });
 }
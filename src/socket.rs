@@ -0,0 +1,306 @@
+use crate::api::{ask_app, ApiCommand, ApiReply, ApiRequest};
+use crate::constants::{API_MAX_REQUEST, API_POLL_INTERVAL, SOCKET_NAME};
+use crate::user::TimeFormat;
+
+use std::env;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use serde_json::{json, Value};
+
+/// Where the control socket goes. `None` if there's no `XDG_RUNTIME_DIR`.
+pub fn socket_path() -> Option<PathBuf> {
+    match env::var_os("XDG_RUNTIME_DIR") {
+        Some(dir) if !dir.is_empty() => Some(PathBuf::from(dir).join(SOCKET_NAME)),
+        _ => None,
+    }
+}
+
+/// A Unix socket that takes one JSON command per line, see `ApiCommand`, and
+/// answers each with one line of JSON. Meant for status bars and shell prompts.
+/// The socket is removed when this is dropped.
+pub struct SocketServer {
+    pub path: PathBuf,
+    pub requests: Receiver<ApiRequest>,
+    stop: Arc<AtomicBool>,
+}
+
+impl SocketServer {
+    /// Starts listening on `path`. Fails if another actt is already listening
+    /// there.
+    #[cfg(unix)]
+    pub fn start(path: PathBuf, ctx: egui::Context) -> io::Result<Self> {
+        use std::os::unix::fs::PermissionsExt;
+        use std::os::unix::net::{UnixListener, UnixStream};
+
+        let listener = match UnixListener::bind(&path) {
+            Ok(listener) => listener,
+            Err(e) if e.kind() == io::ErrorKind::AddrInUse => {
+                // Left behind by an actt that didn't close cleanly, unless one
+                // is still running.
+                if UnixStream::connect(&path).is_ok() {
+                    return Err(e);
+                }
+                fs::remove_file(&path)?;
+                UnixListener::bind(&path)?
+            }
+            Err(e) => return Err(e),
+        };
+
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o600))?;
+        listener.set_nonblocking(true)?;
+
+        let (sender, requests) = mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
+        {
+            let stop = stop.clone();
+            thread::spawn(move || {
+                while !stop.load(Ordering::Relaxed) {
+                    match listener.accept() {
+                        Ok((stream, _)) => {
+                            let sender = sender.clone();
+                            let ctx = ctx.clone();
+                            // A client can keep its connection open, so every
+                            // client gets its own thread.
+                            thread::spawn(move || {
+                                let _ = stream
+                                    .set_nonblocking(false)
+                                    .and_then(|_| stream.try_clone())
+                                    .and_then(|writer| {
+                                        handle_client(&stream, writer, &sender, &ctx)
+                                    });
+                            });
+                        }
+                        Err(_) => thread::sleep(API_POLL_INTERVAL),
+                    }
+                }
+            });
+        }
+
+        Ok(Self {
+            path,
+            requests,
+            stop,
+        })
+    }
+
+    #[cfg(not(unix))]
+    pub fn start(_path: PathBuf, _ctx: egui::Context) -> io::Result<Self> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "The control socket needs a Unix system",
+        ))
+    }
+}
+
+impl Drop for SocketServer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        for (_, reply) in self.requests.try_iter() {
+            let _ = reply.send(ApiReply::error(503, "The control socket was closed"));
+        }
+
+        // The listening thread notices `stop` on its own, there's no need to
+        // hold up the window waiting for it.
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Answers every line the client sends until it hangs up.
+fn handle_client(
+    reader: impl Read,
+    mut writer: impl Write,
+    sender: &Sender<ApiRequest>,
+    ctx: &egui::Context,
+) -> io::Result<()> {
+    let mut reader = BufReader::new(reader);
+    let mut line = Vec::new();
+    loop {
+        // The limit is for each command, a client can stay connected for as
+        // long as it likes.
+        line.clear();
+        let read = (&mut reader)
+            .take(API_MAX_REQUEST)
+            .read_until(b'\n', &mut line)?;
+        if read == 0 {
+            return Ok(());
+        }
+        if read as u64 == API_MAX_REQUEST && !line.ends_with(b"\n") {
+            // There's no telling where the next command starts.
+            let reply = ApiReply::error(413, "The command is too long");
+            return writeln!(writer, "{}", reply.body);
+        }
+
+        let line = String::from_utf8_lossy(&line);
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let reply = match serde_json::from_str::<ApiCommand>(&line) {
+            Ok(command) => ask_app(command, sender, ctx),
+            Err(e) => ApiReply::error(400, &format!("Malformed command: {}", e)),
+        };
+        writeln!(writer, "{}", reply.body)?;
+    }
+}
+
+/// Runs `actt ctl`, which sends one command to the running actt.
+///
+/// # Return
+/// Returns the exit code.
+pub fn run_client(words: &[String]) -> i32 {
+    let raw = words.iter().any(|word| word == "--json");
+    let command = match client_command(words) {
+        Ok(command) => command,
+        Err(msg) => {
+            eprintln!("{}\n\n{}", msg, crate::cli::USAGE);
+            return 2;
+        }
+    };
+
+    let Some(path) = socket_path() else {
+        eprintln!("XDG_RUNTIME_DIR isn't set, so there is no control socket.");
+        return 1;
+    };
+
+    let reply = match send(&path, &command) {
+        Ok(reply) => reply,
+        Err(e) => {
+            eprintln!("Couldn't reach actt at {}: {}", path.display(), e);
+            return 1;
+        }
+    };
+
+    if raw {
+        println!("{}", reply);
+    }
+
+    if let Some(error) = reply.get("error").and_then(Value::as_str) {
+        if !raw {
+            eprintln!("{}", error);
+        }
+        return 1;
+    }
+
+    if !raw && command["command"] == "status" {
+        println!("{}", status_line(&reply));
+    }
+
+    0
+}
+
+/// Turns the words after `ctl` into the JSON sent over the socket.
+fn client_command(words: &[String]) -> Result<Value, String> {
+    let mut words = words.iter().filter(|word| *word != "--json");
+    let command = words.next().ok_or("ctl needs a command")?;
+    match command.as_str() {
+        "status" | "pause" | "resume" | "stop" => Ok(json!({ "command": command })),
+        "start" => {
+            let mut name = Vec::new();
+            let mut tag = String::new();
+            while let Some(word) = words.next() {
+                match word.as_str() {
+                    "--tag" => tag = words.next().ok_or("--tag needs a tag")?.clone(),
+                    _ => name.push(word.as_str()),
+                }
+            }
+
+            if name.is_empty() {
+                return Err("start needs the name of an activity".to_string());
+            }
+            Ok(json!({ "command": "start", "name": name.join(" "), "tag": tag }))
+        }
+        _ => Err(format!("Unknown command: {}", command)),
+    }
+}
+
+#[cfg(unix)]
+fn send(path: &Path, command: &Value) -> io::Result<Value> {
+    let mut stream = std::os::unix::net::UnixStream::connect(path)?;
+    stream.set_read_timeout(Some(Duration::from_secs(10)))?;
+    writeln!(stream, "{}", command)?;
+
+    let mut line = String::new();
+    BufReader::new(stream).read_line(&mut line)?;
+    serde_json::from_str(&line).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+#[cfg(not(unix))]
+fn send(_path: &Path, _command: &Value) -> io::Result<Value> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "The control socket needs a Unix system",
+    ))
+}
+
+/// The status the way a status bar shows it, e.g. `Write docs [work] 01:02:03`.
+fn status_line(status: &Value) -> String {
+    let state = status["state"].as_str().unwrap_or_default();
+    if state == "idle" {
+        return "Idle".to_string();
+    }
+
+    let mut line = status["activity"].as_str().unwrap_or_default().to_string();
+    if let Some(tag) = status["tag"].as_str().filter(|tag| !tag.is_empty()) {
+        line.push_str(&format!(" [{}]", tag));
+    }
+
+    let elapsed = Duration::from_secs(status["elapsed_seconds"].as_u64().unwrap_or_default());
+    line.push(' ');
+    line.push_str(&TimeFormat::Clock.format(elapsed));
+    if state == "paused" {
+        line.push_str(" (paused)");
+    }
+
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn command(words: &str) -> Result<Value, String> {
+        let words: Vec<String> = words.split_whitespace().map(String::from).collect();
+        client_command(&words)
+    }
+
+    #[test]
+    fn simple_commands() {
+        for name in ["status", "pause", "resume", "stop"] {
+            assert_eq!(command(name), Ok(json!({ "command": name })));
+        }
+        assert_eq!(command("--json status"), Ok(json!({ "command": "status" })));
+    }
+
+    #[test]
+    fn start_takes_a_name_and_a_tag() {
+        assert_eq!(
+            command("start write docs --tag client/docs --json"),
+            Ok(json!({ "command": "start", "name": "write docs", "tag": "client/docs" }))
+        );
+        assert_eq!(
+            command("start --tag home tidy up"),
+            Ok(json!({ "command": "start", "name": "tidy up", "tag": "home" }))
+        );
+        assert_eq!(
+            command("start reading"),
+            Ok(json!({ "command": "start", "name": "reading", "tag": "" }))
+        );
+    }
+
+    #[test]
+    fn rejects_incomplete_commands() {
+        assert!(command("").is_err());
+        assert!(command("--json").is_err());
+        assert!(command("start").is_err());
+        assert!(command("start --tag home").is_err());
+        assert!(command("start reading --tag").is_err());
+        assert!(command("restart").is_err());
+    }
+}
@@ -1,45 +1,76 @@
 use super::Preferences;
 use super::Entry;
+use super::Favorite;
+use super::Goal;
+use super::Rate;
+use super::TagNode;
+use super::TagPalette;
+use super::Timesheet;
+
+use crate::constants::{
+    color_distance, contrast_ratio, from_oklab, MIN_COLOR_DISTANCE, MIN_TAG_CONTRAST,
+};
 
 use std::time::Duration;
 
-use rand::Rng;
 use egui::Color32;
+use chrono::{DateTime, Local, NaiveDate, Weekday};
 
 use derivative::Derivative;
 use serde::{Deserialize, Serialize};
 
 // When a new field is added remember to add the change in the delete logic.
 // This also applies to the stop logic for adding entries to the config file.
-#[derive(Derivative, Deserialize, Serialize, Default)]
+#[derive(Derivative, Deserialize, Serialize, Default, Clone)]
 pub struct Config {
     // Activity entry
     pub entry: Vec<Entry>,
     pub total_time: Vec<Duration>,
     pub tag_list: Vec<String>,
     pub colors: Vec<Color32>,
+    /// Time goals for each tag, stored at the same index as the tag.
+    #[serde(default)]
+    pub tag_goals: Vec<Vec<Goal>>,
+    /// Hourly rates for each tag, stored at the same index as the tag. Sub-tags
+    /// without a rate are billed at the rate of their parent.
+    #[serde(default)]
+    pub tag_rates: Vec<Option<Rate>>,
+    /// The first day of every week whose timesheet was submitted, which locks it.
+    #[serde(default)]
+    pub submitted_weeks: Vec<NaiveDate>,
+    /// Activities the user pinned to the start screen.
+    #[serde(default)]
+    pub favorites: Vec<Favorite>,
 
-    /// Maybe this should be stored in its own file instead of instead of config?
+    /// Preferences are stored in `settings.toml` now. This is only read to carry
+    /// them over from config files made before that.
     #[derivative(Default(value = "Preferences::default()"))]
+    #[serde(default, skip_serializing)]
     pub preferences: Preferences,
 }
 
 // TODO: Most of these should be in a utillities module honestly.
 impl Config {
-    /// `value` can be either `"random"` or `"picker"`
-    pub fn tag_assign_behavior(&self) -> &String {
-        &self.preferences.tag_assign_behavior
-    }
-
-    /// Finds the specified tag in the config file.
+    /// Finds the specified tag in the config file. Tags are the same if they only
+    /// differ in case or whitespace, see `Config::normalize_tag`.
     ///
     /// # Return
     /// Returns `Some(n)` where `n` is the index of the tag if found and
     /// `None` if the tag doesn't exist.
-    pub fn find_tag(&self, tag_list: &[String], tag_to_find: &String) -> Option<usize> {
+    pub fn find_tag(&self, tag_list: &[String], tag_to_find: &str) -> Option<usize> {
+        let tag_to_find = Self::normalize_tag(tag_to_find);
         tag_list
             .iter()
-            .position(|e| e == tag_to_find)
+            .position(|e| Self::normalize_tag(e) == tag_to_find)
+    }
+
+    /// Lowercases `tag` and collapses its whitespace, so `" Work  stuff"` and
+    /// `"work stuff"` are the same tag.
+    pub fn normalize_tag(tag: &str) -> String {
+        tag.split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .to_lowercase()
     }
 
     /// Finds the specified tag in the config file.
@@ -53,6 +84,306 @@ impl Config {
             .position(|e| e == color_to_find)
     }
 
+    /// Finds `tag`, creating it with a color that isn't taken yet if it doesn't exist.
+    /// See `Config::pick_color` for how the color is chosen.
+    ///
+    /// # Return
+    /// Returns `(tag_index, color_index)`.
+    pub fn ensure_tag(
+        &mut self,
+        tag: &str,
+        color: &Color32,
+        palette: TagPalette,
+        background: Color32,
+    ) -> (usize, usize) {
+        // Tags and their colors are stored at the same index.
+        if let Some(tag_index) = self.find_tag(&self.tag_list, tag) {
+            if tag_index < self.colors.len() {
+                return (tag_index, tag_index);
+            }
+        }
+
+        let color = self.pick_color(&self.colors, color, palette, background);
+        self.colors.push(color);
+        let color_index = self.colors.len() - 1;
+
+        let tag_index = match self.find_tag(&self.tag_list, tag) {
+            Some(tag_index) => tag_index,
+            None => {
+                self.tag_list.push(tag.to_string());
+                self.tag_list.len() - 1
+            }
+        };
+
+        (tag_index, color_index)
+    }
+
+    /// Finds every tag in `tags`, creating the ones that don't exist yet.
+    /// See `Config::ensure_tag`.
+    pub fn ensure_tags(
+        &mut self,
+        tags: &[String],
+        color: &Color32,
+        palette: TagPalette,
+        background: Color32,
+    ) -> Vec<usize> {
+        tags.iter()
+            .filter(|tag| !tag.trim().is_empty())
+            .map(|tag| self.ensure_tag(tag.trim(), color, palette, background).0)
+            .collect()
+    }
+
+    /// Whether the name, notes or any tag of `entry` contains `query`, ignoring case.
+    pub fn entry_matches(&self, entry: &Entry, query: &str) -> bool {
+        let query = query.trim().to_lowercase();
+        let contains = |text: &str| text.to_lowercase().contains(&query);
+
+        contains(&entry.name)
+            || contains(&entry.notes)
+            || entry
+                .tag_indexes()
+                .filter_map(|tag_index| self.tag_list.get(tag_index))
+                .any(|tag| contains(tag))
+    }
+
+    /// The names of the tags of `entry` besides the primary one.
+    pub fn extra_tag_names(&self, entry: &Entry) -> Vec<String> {
+        entry
+            .extra_tags
+            .iter()
+            .filter_map(|tag_index| self.tag_list.get(*tag_index))
+            .map(|tag| tag.trim().to_string())
+            .collect()
+    }
+
+    /// The time goals of the tag at `tag_index`.
+    pub fn goals(&self, tag_index: usize) -> &[Goal] {
+        match self.tag_goals.get(tag_index) {
+            Some(goals) => goals,
+            None => &[],
+        }
+    }
+
+    /// The time goals of the tag at `tag_index`, for editing.
+    pub fn goals_mut(&mut self, tag_index: usize) -> &mut Vec<Goal> {
+        // Configs from before goals existed don't have an entry for every tag.
+        if self.tag_goals.len() <= tag_index {
+            self.tag_goals.resize(tag_index + 1, Vec::new());
+        }
+
+        &mut self.tag_goals[tag_index]
+    }
+
+    /// The rate set on the tag at `tag_index` itself.
+    pub fn rate(&self, tag_index: usize) -> Option<&Rate> {
+        self.tag_rates.get(tag_index).and_then(|rate| rate.as_ref())
+    }
+
+    pub fn set_rate(&mut self, tag_index: usize, rate: Option<Rate>) {
+        // Configs from before rates existed don't have an entry for every tag.
+        if self.tag_rates.len() <= tag_index {
+            self.tag_rates.resize(tag_index + 1, None);
+        }
+
+        self.tag_rates[tag_index] = rate;
+    }
+
+    /// The rate the tag at `tag_index` is billed at, which is its own or the one
+    /// of its closest parent that has a rate.
+    ///
+    /// # Return
+    /// Returns `Some((n, rate))` where `n` is the index of the tag the rate is set on.
+    pub fn billing_rate(&self, tag_index: usize) -> Option<(usize, &Rate)> {
+        if let Some(rate) = self.rate(tag_index) {
+            return Some((tag_index, rate));
+        }
+
+        // Parents are matched by their parts, as `Client / Project` is below `client`.
+        let tag = self.tag_list.get(tag_index)?;
+        let depth = TagNode::split(tag).len();
+        (1..depth).rev().find_map(|depth| {
+            self.tag_list
+                .iter()
+                .enumerate()
+                .filter(|(_, parent)| {
+                    TagNode::split(parent).len() == depth && TagNode::is_within(tag, parent)
+                })
+                .find_map(|(index, _)| self.rate(index).map(|rate| (index, rate)))
+        })
+    }
+
+    /// How much time was spent on the tag at `tag_index` within `from..to`.
+    pub fn tag_time_between(
+        &self,
+        tag_index: usize,
+        from: DateTime<Local>,
+        to: DateTime<Local>,
+    ) -> Duration {
+        self.entry
+            .iter()
+            .filter(|entry| entry.has_tag(tag_index))
+            .map(|entry| entry.time_between(from, to))
+            .sum()
+    }
+
+    /// How much time was spent on `tag` and every tag below it. Entries with more
+    /// than one of those tags are only counted once.
+    pub fn rollup_time(&self, tag: &str) -> Duration {
+        self.entry
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| {
+                entry.tag_indexes().any(|tag_index| {
+                    self.tag_list
+                        .get(tag_index)
+                        .is_some_and(|entry_tag| TagNode::is_within(entry_tag, tag))
+                })
+            })
+            .map(|(index, entry)| {
+                self.total_time
+                    .get(index)
+                    .copied()
+                    .unwrap_or(entry.work_time)
+            })
+            .sum()
+    }
+
+    /// Changes how much time the activity `name` under the tag at `tag_index` has
+    /// within the `day` of `week` to `time`, both given as `(start, end)`. Extra
+    /// time is added as a new session of the latest entry of the activity in the
+    /// week, or as a new entry if there is none. Time is taken off the latest
+    /// sessions first.
+    pub fn set_time_between(
+        &mut self,
+        name: &str,
+        tag_index: usize,
+        color_index: usize,
+        day: (DateTime<Local>, DateTime<Local>),
+        week: (DateTime<Local>, DateTime<Local>),
+        time: Duration,
+    ) {
+        let (from, to) = day;
+        let is_row = |entry: &Entry| entry.name == name && entry.tag_index == tag_index;
+        let current: Duration = self
+            .entry
+            .iter()
+            .filter(|entry| is_row(entry))
+            .map(|entry| entry.time_between(from, to))
+            .sum();
+
+        if time > current {
+            let extra = time - current;
+            // There is no way to tell when the time was spent, so it goes at the
+            // start of a work day.
+            let started_at = from + chrono::Duration::hours(9);
+
+            // Entries without a start time for every session can't take new ones,
+            // and entries of other weeks would end up spanning more than one.
+            let latest = self.entry.iter().rposition(|entry| {
+                is_row(entry)
+                    && entry.sessions.len() == entry.session_starts.len()
+                    && entry
+                        .started_at()
+                        .is_some_and(|started_at| week.0 <= started_at && started_at < week.1)
+            });
+            match latest {
+                Some(index) => {
+                    self.entry[index].add_session(started_at, extra, Vec::new());
+                    if let Some(total_time) = self.total_time.get_mut(index) {
+                        *total_time += extra;
+                    }
+                }
+                None => {
+                    let name = name.to_string();
+                    let entry =
+                        Entry::new(name, tag_index, color_index, started_at, extra, Vec::new());
+                    self.entry.push(entry);
+                    self.total_time.push(extra);
+                }
+            }
+        } else {
+            let mut left = current - time;
+            for index in (0..self.entry.len()).rev() {
+                if left.is_zero() {
+                    break;
+                }
+
+                if !is_row(&self.entry[index]) {
+                    continue;
+                }
+
+                let removed = self.entry[index].remove_time_between(from, to, left);
+                left -= removed;
+                if let Some(total_time) = self.total_time.get_mut(index) {
+                    *total_time = total_time.saturating_sub(removed);
+                }
+
+                let entry = &self.entry[index];
+                if entry.sessions.is_empty() && entry.work_time.is_zero() {
+                    self.entry.remove(index);
+                    if index < self.total_time.len() {
+                        self.total_time.remove(index);
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn is_week_submitted(&self, week_start: NaiveDate) -> bool {
+        self.submitted_weeks.contains(&week_start)
+    }
+
+    /// Whether `time` falls in a submitted week, weeks starting on `week_start`.
+    pub fn is_time_submitted(&self, time: DateTime<Local>, week_start: Weekday) -> bool {
+        self.is_week_submitted(Timesheet::week_of(time.date_naive(), week_start))
+    }
+
+    /// Locks the timesheet of the week starting at `week_start`, or unlocks it.
+    pub fn set_week_submitted(&mut self, week_start: NaiveDate, submitted: bool) {
+        self.submitted_weeks.retain(|week| *week != week_start);
+        if submitted {
+            self.submitted_weeks.push(week_start);
+        }
+    }
+
+    /// The unique name and tag pairs of past activities, most recent first.
+    pub fn recent_activities(&self, limit: usize) -> Vec<Favorite> {
+        let mut recent: Vec<Favorite> = Vec::new();
+        for entry in self.entry.iter().rev() {
+            if recent.len() == limit {
+                break;
+            }
+
+            let tag = self
+                .tag_list
+                .get(entry.tag_index)
+                .map(|tag| tag.trim().to_string())
+                .unwrap_or_default();
+            let activity = Favorite::new(entry.name.clone(), tag);
+            if !recent.contains(&activity) {
+                recent.push(activity);
+            }
+        }
+
+        recent
+    }
+
+    /// The unique names of past activities, most recent first.
+    pub fn activity_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = Vec::new();
+        for entry in self.entry.iter().rev() {
+            if !names.contains(&entry.name) {
+                names.push(entry.name.clone());
+            }
+        }
+
+        names
+    }
+
+    pub fn is_favorite(&self, activity: &Favorite) -> bool {
+        self.favorites.contains(activity)
+    }
+
     pub fn does_color_exist(&self, colors: &[Color32], color: &Color32) -> bool {
         if colors.contains(&color) {
             true
@@ -61,25 +392,245 @@ impl Config {
         }
     }
 
-    pub fn random_color(
+    /// Whether `color` can be told apart from every color in `colors`.
+    pub fn is_distinct_color(&self, colors: &[Color32], color: &Color32) -> bool {
+        colors
+            .iter()
+            .all(|other| color_distance(*other, *color) >= MIN_COLOR_DISTANCE)
+    }
+
+    /// Finds the tag color that looks the most like `color`.
+    ///
+    /// # Return
+    /// Returns `Some((n, distance))` where `n` is the index of the color, and
+    /// `None` if there are no colors yet.
+    pub fn closest_color(&self, color: &Color32) -> Option<(usize, f32)> {
+        self.colors
+            .iter()
+            .map(|other| color_distance(*other, *color))
+            .enumerate()
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+    }
+
+    /// Whether `color` can be told apart from `list_of_colors` and read on `background`.
+    pub fn is_usable_color(
+        &self,
+        list_of_colors: &[Color32],
+        color: &Color32,
+        background: Color32,
+    ) -> bool {
+        contrast_ratio(*color, background) >= MIN_TAG_CONTRAST
+            && self.is_distinct_color(list_of_colors, color)
+    }
+
+    /// Picks a color for a new tag. `color` is kept if it can be told apart from
+    /// `list_of_colors` and read on `background`. Otherwise the first such color of
+    /// `palette` is used, and once those run out the generated color that is the
+    /// furthest away from every other one.
+    pub fn pick_color(
         &self,
         list_of_colors: &[Color32],
         color: &Color32,
-        count: Option<usize>,
+        palette: TagPalette,
+        background: Color32,
     ) -> Color32 {
-        let limit = 256 ^ 3;
-        let count = count.unwrap_or(0) + 1;
-        let limit_not_reached = !(limit == count);
-        let color_exists = self.does_color_exist(list_of_colors, color);
+        let is_usable = |color: &Color32| self.is_usable_color(list_of_colors, color, background);
 
-        if color_exists && limit_not_reached {
-            let r = rand::thread_rng().gen_range(0..=255);
-            let g = rand::thread_rng().gen_range(0..=255);
-            let b = rand::thread_rng().gen_range(0..=255);
+        if is_usable(color) {
+            return *color;
+        }
 
-            self.random_color(list_of_colors, &Color32::from_rgb(r, g, b), Some(count))
-        } else {
-            color.clone()
+        if let Some(color) = palette.colors().iter().find(|color| is_usable(color)) {
+            return *color;
         }
+
+        // Colors of the same chroma spread around the hue circle, at a few lightness
+        // levels. The ones that can't be read on the background are left out.
+        let mut candidates = Vec::new();
+        for lightness in [0.75, 0.65, 0.85, 0.55] {
+            for step in 0..24 {
+                let hue = (step as f32 * 15.0).to_radians();
+                let oklab = [lightness, 0.13 * hue.cos(), 0.13 * hue.sin()];
+                if let Some(candidate) = from_oklab(oklab) {
+                    if contrast_ratio(candidate, background) >= MIN_TAG_CONTRAST {
+                        candidates.push(candidate);
+                    }
+                }
+            }
+        }
+
+        let distance_to_others = |candidate: &Color32| {
+            list_of_colors
+                .iter()
+                .map(|other| color_distance(*other, *candidate))
+                .fold(f32::MAX, f32::min)
+        };
+
+        candidates
+            .into_iter()
+            .max_by(|a, b| distance_to_others(a).total_cmp(&distance_to_others(b)))
+            .unwrap_or(*color)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use chrono::TimeZone;
+
+    fn day(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 5, day).unwrap()
+    }
+
+    fn at(day: u32, hour: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(2024, 5, day, hour, 0, 0).unwrap()
+    }
+
+    fn hours(hours: u64) -> Duration {
+        Duration::from_secs(hours * 3600)
+    }
+
+    fn config(entries: Vec<Entry>) -> Config {
+        Config {
+            total_time: entries.iter().map(|entry| entry.work_time).collect(),
+            entry: entries,
+            ..Config::default()
+        }
+    }
+
+    /// Sets the time of `writing` on `day`, in the week starting on Monday the 13th.
+    fn set_time(config: &mut Config, day_of_month: u32, time: Duration) {
+        config.set_time_between(
+            "writing",
+            0,
+            0,
+            Timesheet::day_bounds(day(day_of_month)),
+            Timesheet::week_bounds(day(13)),
+            time,
+        );
+    }
+
+    fn writing(started_at: DateTime<Local>, time: Duration) -> Entry {
+        Entry::new("writing".to_string(), 0, 0, started_at, time, Vec::new())
+    }
+
+    #[test]
+    fn extra_time_goes_to_the_latest_entry_of_the_week() {
+        let mut config = config(vec![writing(at(13, 10), hours(1))]);
+        set_time(&mut config, 14, hours(2));
+
+        assert_eq!(config.entry.len(), 1);
+        assert_eq!(config.entry[0].sessions, [hours(1), hours(2)]);
+        assert_eq!(config.entry[0].session_starts[1], at(14, 9));
+        assert_eq!(config.total_time, [hours(3)]);
+    }
+
+    #[test]
+    fn extra_time_is_added_to_the_time_already_there() {
+        let mut config = config(vec![writing(at(14, 10), hours(1))]);
+        set_time(&mut config, 14, hours(3));
+
+        let (from, to) = Timesheet::day_bounds(day(14));
+        assert_eq!(config.entry[0].time_between(from, to), hours(3));
+        assert_eq!(config.total_time, [hours(3)]);
+    }
+
+    #[test]
+    fn entries_of_other_weeks_are_left_alone() {
+        let mut config = config(vec![writing(at(8, 10), hours(1))]);
+        set_time(&mut config, 14, hours(2));
+
+        assert_eq!(config.entry.len(), 2);
+        assert_eq!(config.entry[0].sessions, [hours(1)]);
+        assert_eq!(config.entry[1].sessions, [hours(2)]);
+        assert_eq!(config.total_time, [hours(1), hours(2)]);
+    }
+
+    #[test]
+    fn time_is_taken_off_the_latest_sessions_first() {
+        let mut entry = writing(at(14, 9), hours(1));
+        entry.add_session(at(14, 13), hours(2), Vec::new());
+        let mut config = config(vec![entry]);
+
+        set_time(&mut config, 14, Duration::from_secs(30 * 60));
+        assert_eq!(config.entry[0].sessions, [Duration::from_secs(30 * 60)]);
+        assert_eq!(config.entry[0].session_starts, [at(14, 9)]);
+        assert_eq!(config.total_time, [Duration::from_secs(30 * 60)]);
+
+        set_time(&mut config, 14, Duration::ZERO);
+        assert!(config.entry.is_empty());
+        assert!(config.total_time.is_empty());
+    }
+
+    #[test]
+    fn other_days_and_activities_keep_their_time() {
+        let mut reading = writing(at(14, 10), hours(1));
+        reading.name = "reading".to_string();
+        let mut config = config(vec![writing(at(13, 10), hours(1)), reading]);
+
+        set_time(&mut config, 14, Duration::ZERO);
+        assert_eq!(config.entry.len(), 2);
+        assert_eq!(config.total_time, [hours(1), hours(1)]);
+    }
+
+    #[test]
+    fn tags_and_colors_stay_parallel() {
+        let mut config = Config {
+            tag_list: vec!["reading".to_string()],
+            colors: vec![Color32::RED],
+            ..Config::default()
+        };
+        let background = Color32::BLACK;
+
+        // An existing tag keeps its color, whatever color was asked for.
+        let found = config.ensure_tag("Reading", &Color32::BLUE, TagPalette::Generated, background);
+        assert_eq!(found, (0, 0));
+        assert_eq!(config.colors, [Color32::RED]);
+
+        let created =
+            config.ensure_tag("writing", &Color32::BLUE, TagPalette::Generated, background);
+        assert_eq!(created, (1, 1));
+        assert_eq!(config.tag_list.len(), config.colors.len());
+    }
+
+    #[test]
+    fn spaced_tags_are_billed_at_their_own_or_parent_rate() {
+        let mut config = Config {
+            tag_list: vec!["Client".to_string(), "Client / Project".to_string()],
+            ..Config::default()
+        };
+        let billed = |config: &Config, tag_index| {
+            config
+                .billing_rate(tag_index)
+                .map(|(index, rate)| (index, rate.hourly))
+        };
+        let rate = |hourly| Rate {
+            hourly,
+            ..Rate::default()
+        };
+        assert_eq!(billed(&config, 1), None);
+
+        config.set_rate(0, Some(rate(50.0)));
+        assert_eq!(billed(&config, 1), Some((0, 50.0)));
+
+        config.set_rate(1, Some(rate(80.0)));
+        assert_eq!(billed(&config, 1), Some((1, 80.0)));
+        assert_eq!(billed(&config, 0), Some((0, 50.0)));
+    }
+
+    #[test]
+    fn only_time_in_submitted_weeks_is_locked() {
+        let mut config = Config::default();
+        config.set_week_submitted(day(13), true);
+
+        assert!(config.is_time_submitted(at(13, 0), Weekday::Mon));
+        assert!(config.is_time_submitted(at(19, 23), Weekday::Mon));
+        assert!(!config.is_time_submitted(at(20, 9), Weekday::Mon));
+        // Weeks starting on Sunday began the day before.
+        assert!(!config.is_time_submitted(at(15, 9), Weekday::Sun));
+
+        config.set_week_submitted(day(13), false);
+        assert!(!config.is_time_submitted(at(15, 9), Weekday::Mon));
     }
 }
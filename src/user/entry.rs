@@ -1,19 +1,194 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Local};
 use serde::{Deserialize, Serialize};
 
-#[derive(Deserialize, Serialize, Default)]
+#[derive(Deserialize, Serialize, Default, Clone)]
+#[serde(default)]
 pub struct Entry {
     pub name: String,
+    /// The primary tag, which decides the color of the entry.
     pub tag_index: usize,
     pub color_index: usize,
+    /// The indexes of the other tags of the entry.
+    pub extra_tags: Vec<usize>,
+    /// Whether the time shows up in billing reports.
+    #[serde(default = "billable_by_default")]
+    pub billable: bool,
+    /// Free-form notes. Lines starting with `# ` are headings and lines starting
+    /// with `- ` or `* ` are list items.
+    pub notes: String,
+    /// How long the user actually worked on the activity.
+    pub work_time: Duration,
+    /// How long the activity was paused for in total.
+    pub pause_time: Duration,
+    /// The length of every pause taken during the activity, in order.
+    pub pauses: Vec<Duration>,
+    /// How long each session of the activity lasted, when the activity was
+    /// resumed from the history screen.
+    pub sessions: Vec<Duration>,
+    /// When each session in `sessions` was started. Empty for entries made
+    /// before start times were recorded.
+    pub session_starts: Vec<DateTime<Local>>,
+}
+
+// Entries from before billing existed are billable.
+fn billable_by_default() -> bool {
+    true
 }
 
 impl Entry {
-    pub fn new(name: String, tag_index: usize, color_index: usize) -> Self {
+    pub fn new(
+        name: String,
+        tag_index: usize,
+        color_index: usize,
+        started_at: DateTime<Local>,
+        work_time: Duration,
+        pauses: Vec<Duration>,
+    ) -> Self {
         Self {
             name,
             tag_index,
             color_index,
+            extra_tags: Vec::new(),
+            billable: true,
+            notes: String::new(),
+            work_time,
+            pause_time: pauses.iter().sum(),
+            pauses,
+            sessions: vec![work_time],
+            session_starts: vec![started_at],
         }
     }
-}
 
+    /// Adds another session of the same activity onto this entry.
+    pub fn add_session(
+        &mut self,
+        started_at: DateTime<Local>,
+        work_time: Duration,
+        pauses: Vec<Duration>,
+    ) {
+        // Entries made before sessions were tracked only ever had one.
+        if self.sessions.is_empty() {
+            self.sessions.push(self.work_time);
+        }
+
+        // Start times are paired up with sessions by position, so they can only be
+        // kept while every earlier session has one.
+        if self.session_starts.len() == self.sessions.len() {
+            self.session_starts.push(started_at);
+        }
+
+        self.sessions.push(work_time);
+        self.work_time += work_time;
+        self.pause_time += pauses.iter().sum::<Duration>();
+        self.pauses.extend(pauses);
+    }
+
+    /// Replaces the tags besides the primary one, dropping duplicates.
+    pub fn set_extra_tags(&mut self, tags: Vec<usize>) {
+        self.extra_tags.clear();
+        for tag_index in tags {
+            if !self.has_tag(tag_index) {
+                self.extra_tags.push(tag_index);
+            }
+        }
+    }
+
+    /// The primary tag followed by the other tags.
+    pub fn tag_indexes(&self) -> impl Iterator<Item = usize> + '_ {
+        std::iter::once(self.tag_index).chain(self.extra_tags.iter().copied())
+    }
+
+    pub fn has_tag(&self, tag_index: usize) -> bool {
+        self.tag_indexes().any(|index| index == tag_index)
+    }
+
+    /// Forgets the tag at `tag_index` after it was removed from the tag list
+    /// along with its color, moving the indexes after it down by one.
+    ///
+    /// # Return
+    /// Returns whether it was the primary tag, which then has to be replaced.
+    pub fn remove_tag(&mut self, tag_index: usize) -> bool {
+        let shift = |index: &mut usize| {
+            if *index > tag_index {
+                *index -= 1;
+            }
+        };
+
+        self.extra_tags.retain(|index| *index != tag_index);
+        self.extra_tags.iter_mut().for_each(shift);
+
+        let was_primary = self.tag_index == tag_index;
+        shift(&mut self.tag_index);
+        shift(&mut self.color_index);
+        was_primary
+    }
+
+    /// Takes up to `time` off the sessions started within `from..to`, the latest
+    /// sessions first. Sessions left without any time are removed.
+    ///
+    /// # Return
+    /// Returns how much time was taken off.
+    pub fn remove_time_between(
+        &mut self,
+        from: DateTime<Local>,
+        to: DateTime<Local>,
+        time: Duration,
+    ) -> Duration {
+        let mut left = time;
+        let sessions = self.sessions.iter_mut().zip(&self.session_starts);
+        for (session, start) in sessions.rev() {
+            if left.is_zero() {
+                break;
+            }
+
+            if from <= *start && *start < to {
+                let taken = (*session).min(left);
+                *session -= taken;
+                left -= taken;
+            }
+        }
+
+        let mut index = 0;
+        while index < self.sessions.len().min(self.session_starts.len()) {
+            if self.sessions[index].is_zero() {
+                self.sessions.remove(index);
+                self.session_starts.remove(index);
+            } else {
+                index += 1;
+            }
+        }
+
+        let removed = time - left;
+        self.work_time = self.work_time.saturating_sub(removed);
+        removed
+    }
+
+    /// When the activity was first started, if it is known.
+    pub fn started_at(&self) -> Option<DateTime<Local>> {
+        self.session_starts.first().copied()
+    }
+
+    /// How much time was spent on the activity in sessions started within `from..to`.
+    pub fn time_between(&self, from: DateTime<Local>, to: DateTime<Local>) -> Duration {
+        self.sessions
+            .iter()
+            .zip(&self.session_starts)
+            .filter(|(_, start)| from <= **start && **start < to)
+            .map(|(work_time, _)| *work_time)
+            .sum()
+    }
+
+    /// The part of `work_time` from sessions that have no start time, because
+    /// they were tracked before start times were recorded.
+    pub fn untimed_work_time(&self) -> Duration {
+        let timed: Duration = self.sessions.iter().take(self.session_starts.len()).sum();
+        self.work_time.saturating_sub(timed)
+    }
+
+    /// The number of times the activity was tracked.
+    pub fn session_count(&self) -> usize {
+        self.sessions.len().max(1)
+    }
+}
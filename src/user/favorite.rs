@@ -0,0 +1,23 @@
+use serde::{Deserialize, Serialize};
+
+/// An activity name and tag pair that can be started straight from the start screen.
+#[derive(Deserialize, Serialize, Clone, PartialEq, Default)]
+pub struct Favorite {
+    pub name: String,
+    pub tag: String,
+}
+
+impl Favorite {
+    pub fn new(name: String, tag: String) -> Self {
+        Self { name, tag }
+    }
+
+    /// The text shown on the quick start buttons.
+    pub fn label(&self) -> String {
+        if self.tag.trim().is_empty() {
+            self.name.clone()
+        } else {
+            format!("{} ({})", self.name, self.tag)
+        }
+    }
+}
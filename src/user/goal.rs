@@ -0,0 +1,129 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Datelike, Days, Local, NaiveTime, TimeZone};
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq)]
+pub enum GoalKind {
+    /// A target to reach, e.g. "Deep work ≥ 15h/week".
+    AtLeast,
+    /// A budget not to go over, e.g. "Meetings ≤ 6h/week".
+    AtMost,
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq)]
+pub enum GoalPeriod {
+    Daily,
+    Weekly,
+}
+
+/// A time goal for a tag.
+#[derive(Deserialize, Serialize, Clone, PartialEq)]
+pub struct Goal {
+    pub kind: GoalKind,
+    pub period: GoalPeriod,
+    pub hours: f32,
+}
+
+impl Default for Goal {
+    fn default() -> Self {
+        Self {
+            kind: GoalKind::AtLeast,
+            period: GoalPeriod::Weekly,
+            hours: 1.0,
+        }
+    }
+}
+
+impl Goal {
+    pub fn target(&self) -> Duration {
+        Duration::from_secs_f32(self.hours.max(0.0) * 3600.0)
+    }
+
+    /// The goal in a short form, e.g. `"≥ 15h / week"`.
+    pub fn describe(&self) -> String {
+        let kind = match self.kind {
+            GoalKind::AtLeast => "≥",
+            GoalKind::AtMost => "≤",
+        };
+        let period = match self.period {
+            GoalPeriod::Daily => "day",
+            GoalPeriod::Weekly => "week",
+        };
+
+        format!("{} {}h / {}", kind, self.hours, period)
+    }
+
+    /// When the period `now` falls in began. Weeks start on `week_start`, given
+    /// as the number of days from Monday.
+    pub fn period_start(&self, now: DateTime<Local>, week_start: u32) -> DateTime<Local> {
+        let today = now.date_naive();
+        let start = match self.period {
+            GoalPeriod::Daily => today,
+            GoalPeriod::Weekly => {
+                let days = (7 + today.weekday().num_days_from_monday() - week_start) % 7;
+                today - Days::new(days as u64)
+            }
+        };
+
+        let midnight = start.and_time(NaiveTime::MIN);
+        Local
+            .from_local_datetime(&midnight)
+            .earliest()
+            .unwrap_or(now)
+    }
+
+    /// How far along `used` is towards the target, where `1.0` is the target itself.
+    pub fn progress(&self, used: Duration) -> f32 {
+        let target = self.target();
+        if target.is_zero() {
+            1.0
+        } else {
+            used.as_secs_f32() / target.as_secs_f32()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn goal(period: GoalPeriod) -> Goal {
+        Goal {
+            period,
+            ..Goal::default()
+        }
+    }
+
+    /// Wednesday, 15 May 2024 at noon.
+    fn wednesday_noon() -> DateTime<Local> {
+        Local.with_ymd_and_hms(2024, 5, 15, 12, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn daily_periods_start_at_midnight() {
+        let start = goal(GoalPeriod::Daily).period_start(wednesday_noon(), 0);
+        assert_eq!(start, Local.with_ymd_and_hms(2024, 5, 15, 0, 0, 0).unwrap());
+    }
+
+    #[test]
+    fn weekly_periods_start_on_the_week_start() {
+        let weekly = goal(GoalPeriod::Weekly);
+
+        // Monday
+        let start = weekly.period_start(wednesday_noon(), 0);
+        assert_eq!(start, Local.with_ymd_and_hms(2024, 5, 13, 0, 0, 0).unwrap());
+
+        // Wednesday itself
+        let start = weekly.period_start(wednesday_noon(), 2);
+        assert_eq!(start, Local.with_ymd_and_hms(2024, 5, 15, 0, 0, 0).unwrap());
+
+        // Sunday
+        let start = weekly.period_start(wednesday_noon(), 6);
+        assert_eq!(start, Local.with_ymd_and_hms(2024, 5, 12, 0, 0, 0).unwrap());
+
+        // Thursday, so the week began the Thursday before
+        let start = weekly.period_start(wednesday_noon(), 3);
+        assert_eq!(start, Local.with_ymd_and_hms(2024, 5, 9, 0, 0, 0).unwrap());
+    }
+}
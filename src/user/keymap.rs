@@ -0,0 +1,232 @@
+use egui::{Key, Modifiers};
+use serde::{Deserialize, Serialize};
+
+/// Something the user can do with a keyboard shortcut.
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq)]
+pub enum Action {
+    ShowHome,
+    ShowHistory,
+    ShowTags,
+    ShowTimesheet,
+    ShowBilling,
+    ShowSettings,
+    /// Starts the activity filled in on the home screen, or stops the current one.
+    StartStop,
+    PauseResume,
+    FocusActivity,
+    NextRow,
+    PreviousRow,
+    RenameRow,
+    RetagRow,
+    DeleteRow,
+    ShowShortcuts,
+    CommandPalette,
+}
+
+impl Action {
+    /// Every action, in the order they are listed in.
+    pub const ALL: [Action; 16] = [
+        Self::ShowHome,
+        Self::ShowHistory,
+        Self::ShowTags,
+        Self::ShowTimesheet,
+        Self::ShowBilling,
+        Self::ShowSettings,
+        Self::StartStop,
+        Self::PauseResume,
+        Self::FocusActivity,
+        Self::NextRow,
+        Self::PreviousRow,
+        Self::RenameRow,
+        Self::RetagRow,
+        Self::DeleteRow,
+        Self::ShowShortcuts,
+        Self::CommandPalette,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::ShowHome => "Go to Home",
+            Self::ShowHistory => "Go to History",
+            Self::ShowTags => "Go to Tags",
+            Self::ShowTimesheet => "Go to Timesheet",
+            Self::ShowBilling => "Go to Billing",
+            Self::ShowSettings => "Go to Settings",
+            Self::StartStop => "Start or stop",
+            Self::PauseResume => "Pause or resume",
+            Self::FocusActivity => "Type an activity",
+            Self::NextRow => "Next history row",
+            Self::PreviousRow => "Previous history row",
+            Self::RenameRow => "Rename the history row",
+            Self::RetagRow => "Retag the history row",
+            Self::DeleteRow => "Delete the history row",
+            Self::ShowShortcuts => "Show shortcuts",
+            Self::CommandPalette => "Open the command palette",
+        }
+    }
+
+    pub fn default_shortcut(&self) -> Shortcut {
+        match self {
+            Self::ShowHome => Shortcut::ctrl(Key::Num1),
+            Self::ShowHistory => Shortcut::ctrl(Key::Num2),
+            Self::ShowTags => Shortcut::ctrl(Key::Num3),
+            Self::ShowTimesheet => Shortcut::ctrl(Key::Num4),
+            Self::ShowBilling => Shortcut::ctrl(Key::Num5),
+            Self::ShowSettings => Shortcut::ctrl(Key::Num6),
+            Self::StartStop => Shortcut::ctrl(Key::Enter),
+            Self::PauseResume => Shortcut::ctrl(Key::P),
+            Self::FocusActivity => Shortcut::ctrl(Key::L),
+            Self::NextRow => Shortcut::new(Key::ArrowDown),
+            Self::PreviousRow => Shortcut::new(Key::ArrowUp),
+            Self::RenameRow => Shortcut::new(Key::F2),
+            Self::RetagRow => Shortcut::ctrl(Key::T),
+            Self::DeleteRow => Shortcut::new(Key::Delete),
+            Self::ShowShortcuts => Shortcut::new(Key::F1),
+            Self::CommandPalette => Shortcut::ctrl(Key::K),
+        }
+    }
+}
+
+/// A key along with the modifiers that have to be held down with it.
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq)]
+pub struct Shortcut {
+    pub key: Key,
+    /// Ctrl, or Cmd on a Mac.
+    #[serde(default)]
+    pub ctrl: bool,
+    #[serde(default)]
+    pub shift: bool,
+    #[serde(default)]
+    pub alt: bool,
+}
+
+impl Shortcut {
+    pub fn new(key: Key) -> Self {
+        Self {
+            key,
+            ctrl: false,
+            shift: false,
+            alt: false,
+        }
+    }
+
+    pub fn ctrl(key: Key) -> Self {
+        Self {
+            ctrl: true,
+            ..Self::new(key)
+        }
+    }
+
+    pub fn from_input(key: Key, modifiers: Modifiers) -> Self {
+        Self {
+            key,
+            ctrl: modifiers.command,
+            shift: modifiers.shift,
+            alt: modifiers.alt,
+        }
+    }
+
+    pub fn matches(&self, key: Key, modifiers: Modifiers) -> bool {
+        *self == Self::from_input(key, modifiers)
+    }
+
+    /// Whether the shortcut would get in the way of typing into a text edit.
+    pub fn is_typing(&self) -> bool {
+        // `F1` to `F20`, but not the letter `F`.
+        let key = format!("{:?}", self.key);
+        let is_function_key = key.len() > 1 && key.starts_with('F');
+        !self.ctrl && !self.alt && !is_function_key
+    }
+
+    /// The shortcut the way it's shown to the user, e.g. `Ctrl+Shift+P`.
+    pub fn label(&self) -> String {
+        let mut label = String::new();
+        if self.ctrl {
+            label.push_str("Ctrl+");
+        }
+        if self.shift {
+            label.push_str("Shift+");
+        }
+        if self.alt {
+            label.push_str("Alt+");
+        }
+
+        let key = format!("{:?}", self.key);
+        let key = match self.key {
+            Key::ArrowDown => "Down",
+            Key::ArrowLeft => "Left",
+            Key::ArrowRight => "Right",
+            Key::ArrowUp => "Up",
+            Key::Escape => "Esc",
+            Key::PageUp => "Page Up",
+            Key::PageDown => "Page Down",
+            _ => key.strip_prefix("Num").unwrap_or(&key),
+        };
+        label.push_str(key);
+        label
+    }
+}
+
+/// Which shortcut runs which action.
+#[derive(Deserialize, Serialize, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct Keymap {
+    /// Only the shortcuts the user changed. Every other action uses its default.
+    pub bindings: Vec<Binding>,
+}
+
+/// The shortcut the user gave an action. `None` when the action was unbound.
+#[derive(Deserialize, Serialize, Clone, PartialEq)]
+pub struct Binding {
+    pub action: Action,
+    pub shortcut: Option<Shortcut>,
+}
+
+impl Keymap {
+    pub fn shortcut(&self, action: Action) -> Option<Shortcut> {
+        match self
+            .bindings
+            .iter()
+            .find(|binding| binding.action == action)
+        {
+            Some(binding) => binding.shortcut,
+            None => Some(action.default_shortcut()),
+        }
+    }
+
+    /// Binds `action` to `shortcut`, taking the shortcut away from any other action.
+    pub fn set(&mut self, action: Action, shortcut: Option<Shortcut>) {
+        if shortcut.is_some() {
+            for other in Action::ALL {
+                if other != action && self.shortcut(other) == shortcut {
+                    self.bind(other, None);
+                }
+            }
+        }
+
+        self.bind(action, shortcut);
+    }
+
+    fn bind(&mut self, action: Action, shortcut: Option<Shortcut>) {
+        self.bindings.retain(|binding| binding.action != action);
+        if shortcut != Some(action.default_shortcut()) {
+            self.bindings.push(Binding { action, shortcut });
+        }
+    }
+
+    /// The action bound to `key` pressed with `modifiers`.
+    pub fn action(&self, key: Key, modifiers: Modifiers) -> Option<Action> {
+        Action::ALL.into_iter().find(|action| {
+            self.shortcut(*action)
+                .is_some_and(|shortcut| shortcut.matches(key, modifiers))
+        })
+    }
+
+    /// The shortcut of `action` the way it's shown to the user.
+    pub fn label(&self, action: Action) -> String {
+        match self.shortcut(action) {
+            Some(shortcut) => shortcut.label(),
+            None => "None".to_string(),
+        }
+    }
+}
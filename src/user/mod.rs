@@ -2,7 +2,31 @@ mod config;
 pub use config::Config;
 
 mod preferences;
-pub use preferences::Preferences;
+pub use preferences::{Preferences, TagAssignBehavior, TagPalette, Theme, TimeFormat};
 
 mod entry;
 pub use entry::Entry;
+
+mod favorite;
+pub use favorite::Favorite;
+
+mod pomodoro;
+pub use pomodoro::{Pomodoro, PomodoroPhase};
+
+mod goal;
+pub use goal::{Goal, GoalKind, GoalPeriod};
+
+mod rate;
+pub use rate::{Rate, Rounding};
+
+mod tag_node;
+pub use tag_node::TagNode;
+
+mod timesheet;
+pub use timesheet::{Timesheet, TimesheetRow};
+
+mod settings;
+pub use settings::{Settings, DEFAULT_PROFILE};
+
+mod keymap;
+pub use keymap::{Action, Keymap, Shortcut};
@@ -0,0 +1,59 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+/// How long each phase of a pomodoro cycle lasts.
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct Pomodoro {
+    /// Length of a work phase, in minutes.
+    pub work_minutes: u64,
+    /// Length of a short break, in minutes.
+    pub break_minutes: u64,
+    /// Length of a long break, in minutes.
+    pub long_break_minutes: u64,
+    /// A long break is taken instead of a short one after this many work phases.
+    pub long_break_every: u32,
+    /// Plays the desktop's alert sound when a phase ends.
+    pub play_sound: bool,
+}
+
+impl Default for Pomodoro {
+    fn default() -> Self {
+        Self {
+            work_minutes: 25,
+            break_minutes: 5,
+            long_break_minutes: 15,
+            long_break_every: 4,
+            play_sound: false,
+        }
+    }
+}
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum PomodoroPhase {
+    Work,
+    ShortBreak,
+    LongBreak,
+}
+
+impl PomodoroPhase {
+    /// How long this phase lasts with the user's preferences.
+    pub fn length(&self, pomodoro: &Pomodoro) -> Duration {
+        let minutes = match self {
+            Self::Work => pomodoro.work_minutes,
+            Self::ShortBreak => pomodoro.break_minutes,
+            Self::LongBreak => pomodoro.long_break_minutes,
+        };
+
+        Duration::from_secs(minutes * 60)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Work => "Work",
+            Self::ShortBreak => "Short break",
+            Self::LongBreak => "Long break",
+        }
+    }
+}
@@ -1,19 +1,193 @@
+use super::Keymap;
+use super::Pomodoro;
+use super::Rounding;
+
+use crate::constants::{API_DEFAULT_PORT, IBM_PALETTE, OKABE_ITO_PALETTE, TOL_BRIGHT_PALETTE};
+
+use std::time::Duration;
+
+use chrono::Weekday;
+use egui::{Color32, Visuals};
 use serde::{Deserialize, Serialize};
 
+/// What happens when a new tag is given a color that another tag already has.
+/// This is needed when a tag is renamed and the color of the tag already exists.
+/// It occurs when there are a group of activities with the same tag, and one of them has their tag changed.
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq)]
+pub enum TagAssignBehavior {
+    /// Assign a color from the tag palette to avoid the clash. Which means only a text edit to change the name of the tag will appear.
+    #[serde(rename = "random")]
+    Random,
+    /// A window pops up containing a text edit asking for the user to input a new tag name, along with a color picker to change the name of the tag.
+    #[serde(rename = "picker", alias = "choice")]
+    Picker,
+}
+
+impl TagAssignBehavior {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Random => "Pick one for me",
+            Self::Picker => "Let me pick",
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq)]
+pub enum Theme {
+    Dark,
+    Light,
+}
+
+impl Theme {
+    /// The color tags are shown on.
+    pub fn background(&self) -> Color32 {
+        match self {
+            Self::Dark => Visuals::dark().window_fill(),
+            Self::Light => Visuals::light().window_fill(),
+        }
+    }
+}
+
+/// Where the colors of new tags come from.
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq)]
+pub enum TagPalette {
+    /// Colors that are as far from the existing tag colors as possible.
+    Generated,
+    OkabeIto,
+    TolBright,
+    Ibm,
+}
+
+impl TagPalette {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Generated => "Generated",
+            Self::OkabeIto => "Okabe-Ito (colorblind safe)",
+            Self::TolBright => "Tol bright (colorblind safe)",
+            Self::Ibm => "IBM (colorblind safe)",
+        }
+    }
+
+    /// The colors handed out in order, before falling back to generated ones.
+    pub fn colors(&self) -> &'static [Color32] {
+        match self {
+            Self::Generated => &[],
+            Self::OkabeIto => &OKABE_ITO_PALETTE,
+            Self::TolBright => &TOL_BRIGHT_PALETTE,
+            Self::Ibm => &IBM_PALETTE,
+        }
+    }
+}
+
+/// How durations are shown throughout the app.
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq)]
+pub enum TimeFormat {
+    /// `1h 2m 3s`
+    Long,
+    /// `01:02:03`
+    Clock,
+    /// `1.03h`
+    Decimal,
+}
+
+impl TimeFormat {
+    pub fn format(&self, duration: Duration) -> String {
+        let total_time = duration.as_secs();
+        let m = total_time / 60;
+        let s = total_time % 60;
+        let h = m / 60;
+        let m = m % 60;
+
+        match self {
+            Self::Long => format!("{}h {}m {}s", h, m, s),
+            Self::Clock => format!("{:02}:{:02}:{:02}", h, m, s),
+            Self::Decimal => format!("{:.2}h", duration.as_secs_f64() / 3600.0),
+        }
+    }
+}
+
 #[derive(Deserialize, Serialize, Clone)]
+#[serde(default)]
 pub struct Preferences {
-    /// Can be either `"random"` (default) or `"choice"`.
-    /// `"random"` -  Assign a random color to avoid the clash. Which means only a text edit to change the name of the tag will appear.
-    /// `"choice"` A window pops up containing a text edit asking for the user to input a new tag name, along with a color picker to change the name of the tag.
-    /// This is needed when a tag is renamed and the color of the tag already exists.
-    /// It occurs when there are a group of activities with the same tag, and one of them has their tag changed.
-    pub tag_assign_behavior: String,
+    /// Defaults to `TagAssignBehavior::Random`.
+    pub tag_assign_behavior: TagAssignBehavior,
+    pub tag_palette: TagPalette,
+    /// Work and break lengths used in pomodoro mode.
+    pub pomodoro: Pomodoro,
+    /// How many minutes without any input before the user is considered idle.
+    /// `0` turns idle detection off.
+    pub idle_threshold_minutes: u64,
+    pub theme: Theme,
+    pub time_format: TimeFormat,
+    /// The first day of the week, used for weekly goals.
+    pub week_start: Weekday,
+    /// How many backups of the data file to keep. `0` turns backups off.
+    pub backup_retention: usize,
+    /// Entries shorter than this many seconds are thrown away when stopped.
+    pub min_entry_seconds: u64,
+    /// Entries are rounded to this many minutes when stopped. `0` turns rounding off.
+    pub entry_rounding_minutes: u64,
+    pub entry_rounding: Rounding,
+    /// Ask before saving an entry that is shorter than a minute.
+    pub confirm_short_entries: bool,
+    /// Billed time of each entry is rounded to this many minutes. `0` turns rounding off.
+    pub billing_increment_minutes: u64,
+    pub billing_rounding: Rounding,
+    pub keymap: Keymap,
+    /// Whether scripts can control the timer over HTTP on `127.0.0.1`.
+    pub api_enabled: bool,
+    pub api_port: u16,
+    /// Requests to the API have to carry this as a bearer token.
+    pub api_token: String,
+    /// Whether status bars and scripts can use the control socket.
+    pub control_socket: bool,
 }
 
 impl Default for Preferences {
     fn default() -> Self {
         Self {
-            tag_assign_behavior: "random".to_string(),
+            tag_assign_behavior: TagAssignBehavior::Random,
+            tag_palette: TagPalette::Generated,
+            pomodoro: Pomodoro::default(),
+            idle_threshold_minutes: 5,
+            theme: Theme::Dark,
+            time_format: TimeFormat::Long,
+            week_start: Weekday::Mon,
+            backup_retention: 5,
+            min_entry_seconds: 0,
+            entry_rounding_minutes: 0,
+            entry_rounding: Rounding::Nearest,
+            confirm_short_entries: false,
+            billing_increment_minutes: 0,
+            billing_rounding: Rounding::Up,
+            keymap: Keymap::default(),
+            api_enabled: false,
+            api_port: API_DEFAULT_PORT,
+            api_token: String::new(),
+            control_socket: false,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_durations() {
+        let duration = Duration::from_secs(3600 + 2 * 60 + 3);
+        assert_eq!(TimeFormat::Long.format(duration), "1h 2m 3s");
+        assert_eq!(TimeFormat::Clock.format(duration), "01:02:03");
+        assert_eq!(TimeFormat::Decimal.format(duration), "1.03h");
+    }
+
+    #[test]
+    fn formats_zero_and_long_durations() {
+        assert_eq!(TimeFormat::Long.format(Duration::ZERO), "0h 0m 0s");
+        assert_eq!(TimeFormat::Clock.format(Duration::ZERO), "00:00:00");
+        assert_eq!(TimeFormat::Decimal.format(Duration::ZERO), "0.00h");
+
+        let duration = Duration::from_secs(125 * 3600);
+        assert_eq!(TimeFormat::Clock.format(duration), "125:00:00");
+    }
+}
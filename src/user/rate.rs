@@ -0,0 +1,109 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+/// What a tag is billed per hour.
+#[derive(Deserialize, Serialize, Clone, PartialEq)]
+pub struct Rate {
+    pub hourly: f64,
+    /// Shown next to amounts, e.g. `"EUR"`.
+    pub currency: String,
+}
+
+impl Default for Rate {
+    fn default() -> Self {
+        Self {
+            hourly: 0.0,
+            currency: "USD".to_string(),
+        }
+    }
+}
+
+impl Rate {
+    /// How much `time` costs at this rate.
+    pub fn amount(&self, time: Duration) -> f64 {
+        time.as_secs_f64() / 3600.0 * self.hourly
+    }
+}
+
+/// Which way billed time is rounded to the billing increment.
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq)]
+pub enum Rounding {
+    Up,
+    Nearest,
+    Down,
+}
+
+impl Rounding {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Up => "Round up",
+            Self::Nearest => "Round to nearest",
+            Self::Down => "Round down",
+        }
+    }
+
+    /// Rounds `time` to a multiple of `increment_minutes`. `0` leaves it as is.
+    pub fn apply(&self, time: Duration, increment_minutes: u64) -> Duration {
+        let increment = increment_minutes * 60;
+        if increment == 0 {
+            return time;
+        }
+
+        let secs = time.as_secs();
+        let rounded = match self {
+            Self::Up => secs.div_ceil(increment),
+            Self::Nearest => (secs + increment / 2) / increment,
+            Self::Down => secs / increment,
+        };
+
+        Duration::from_secs(rounded * increment)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn minutes(minutes: u64) -> Duration {
+        Duration::from_secs(minutes * 60)
+    }
+
+    #[test]
+    fn zero_increment_leaves_time_as_is() {
+        let time = Duration::from_secs(125);
+        assert_eq!(Rounding::Up.apply(time, 0), time);
+        assert_eq!(Rounding::Nearest.apply(time, 0), time);
+        assert_eq!(Rounding::Down.apply(time, 0), time);
+    }
+
+    #[test]
+    fn multiples_of_the_increment_are_kept() {
+        for rounding in [Rounding::Up, Rounding::Nearest, Rounding::Down] {
+            assert_eq!(rounding.apply(Duration::ZERO, 15), Duration::ZERO);
+            assert_eq!(rounding.apply(minutes(30), 15), minutes(30));
+        }
+    }
+
+    #[test]
+    fn rounds_between_increments() {
+        let time = minutes(16);
+        assert_eq!(Rounding::Up.apply(time, 15), minutes(30));
+        assert_eq!(Rounding::Nearest.apply(time, 15), minutes(15));
+        assert_eq!(Rounding::Down.apply(time, 15), minutes(15));
+
+        let one_second = Duration::from_secs(1);
+        assert_eq!(Rounding::Up.apply(one_second, 15), minutes(15));
+        assert_eq!(Rounding::Down.apply(one_second, 15), Duration::ZERO);
+    }
+
+    #[test]
+    fn nearest_rounds_halfway_up() {
+        let halfway = Duration::from_secs(7 * 60 + 30);
+        assert_eq!(Rounding::Nearest.apply(halfway, 15), minutes(15));
+        assert_eq!(
+            Rounding::Nearest.apply(halfway - Duration::from_secs(1), 15),
+            Duration::ZERO
+        );
+    }
+}
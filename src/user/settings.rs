@@ -0,0 +1,50 @@
+use super::Preferences;
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+/// The profile that uses the original `actt.json` data file.
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Everything that isn't tracking data. Stored in `settings.toml`.
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct Settings {
+    /// The profile whose data file is in use.
+    pub profile: String,
+    /// Every profile the user has made, each one keeps a separate log.
+    pub profiles: Vec<String>,
+    pub preferences: Preferences,
+    /// Where tracking data is kept, if not in the default folder.
+    pub data_dir: Option<PathBuf>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            profile: DEFAULT_PROFILE.to_string(),
+            profiles: vec![DEFAULT_PROFILE.to_string()],
+            preferences: Preferences::default(),
+            data_dir: None,
+        }
+    }
+}
+
+impl Settings {
+    /// The data file of `profile`, inside `data_dir`.
+    pub fn profile_file(data_dir: &Path, profile: &str) -> PathBuf {
+        if profile == DEFAULT_PROFILE {
+            data_dir.join("actt.json")
+        } else {
+            data_dir.join("profiles").join(format!("{}.json", profile))
+        }
+    }
+
+    /// Profile names end up in file names, so they can't contain anything that
+    /// would make them point somewhere else.
+    pub fn is_valid_profile_name(name: &str) -> bool {
+        let forbidden = |c: char| c == '/' || c == '\\' || c == '.' || c.is_control();
+        !name.trim().is_empty() && !name.contains(forbidden)
+    }
+}
@@ -0,0 +1,128 @@
+use super::Config;
+
+use crate::constants::TAG_SEPARATOR;
+
+use std::collections::BTreeMap;
+
+/// One row of the tag tree. Tags can be paths like `client/project/task`, and
+/// every part of a path is a node even when it isn't a tag of its own.
+#[derive(Clone)]
+pub struct TagNode {
+    /// The full path, written the way the user wrote it.
+    pub path: String,
+    /// The index of the tag in `Config::tag_list`. `None` if the node only exists
+    /// because it has children.
+    pub tag_index: Option<usize>,
+    /// How many parents the node has.
+    pub depth: usize,
+    pub has_children: bool,
+}
+
+impl TagNode {
+    /// The last part of the path.
+    pub fn name(&self) -> &str {
+        Self::split(&self.path).last().copied().unwrap_or_default()
+    }
+
+    /// The parts of `tag`, without the whitespace around them.
+    pub fn split(tag: &str) -> Vec<&str> {
+        tag.split(TAG_SEPARATOR)
+            .map(|part| part.trim())
+            .filter(|part| !part.is_empty())
+            .collect()
+    }
+
+    /// Whether `tag` is `ancestor` or somewhere below it, ignoring case and whitespace.
+    pub fn is_within(tag: &str, ancestor: &str) -> bool {
+        let tag = Self::split(tag);
+        let ancestor = Self::split(ancestor);
+        !ancestor.is_empty()
+            && tag.len() >= ancestor.len()
+            && tag
+                .iter()
+                .zip(ancestor.iter())
+                .all(|(a, b)| Config::normalize_tag(a) == Config::normalize_tag(b))
+    }
+
+    /// Arranges `tag_list` into a tree, every node followed by its children.
+    pub fn tree(tag_list: &[String]) -> Vec<TagNode> {
+        let mut nodes: BTreeMap<Vec<String>, TagNode> = BTreeMap::new();
+        for (tag_index, tag) in tag_list.iter().enumerate() {
+            let parts = Self::split(tag);
+            for depth in 0..parts.len() {
+                let key = parts[..=depth]
+                    .iter()
+                    .map(|part| Config::normalize_tag(part))
+                    .collect();
+                let node = nodes.entry(key).or_insert_with(|| TagNode {
+                    path: parts[..=depth].join(&TAG_SEPARATOR.to_string()),
+                    tag_index: None,
+                    depth,
+                    has_children: false,
+                });
+
+                if depth + 1 == parts.len() {
+                    node.tag_index = node.tag_index.or(Some(tag_index));
+                } else {
+                    node.has_children = true;
+                }
+            }
+        }
+
+        nodes.into_values().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tags(tags: &[&str]) -> Vec<String> {
+        tags.iter().map(|tag| tag.to_string()).collect()
+    }
+
+    #[test]
+    fn children_follow_their_parents() {
+        let tree = TagNode::tree(&tags(&["work/acme/design", "home", "work"]));
+        let rows: Vec<_> = tree
+            .iter()
+            .map(|node| {
+                (
+                    node.path.as_str(),
+                    node.tag_index,
+                    node.depth,
+                    node.has_children,
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            rows,
+            [
+                ("home", Some(1), 0, false),
+                ("work", Some(2), 0, true),
+                ("work/acme", None, 1, true),
+                ("work/acme/design", Some(0), 2, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn paths_are_matched_ignoring_case_and_whitespace() {
+        let tree = TagNode::tree(&tags(&["Work / Acme", "work/acme", "work"]));
+
+        assert_eq!(tree.len(), 2);
+        assert_eq!(tree[0].path, "Work");
+        assert_eq!(tree[0].tag_index, Some(2));
+        assert_eq!(tree[1].path, "Work/Acme");
+        assert_eq!(tree[1].tag_index, Some(0));
+        assert_eq!(tree[1].name(), "Acme");
+    }
+
+    #[test]
+    fn empty_parts_are_skipped() {
+        let tree = TagNode::tree(&tags(&["/work//acme/"]));
+        let paths: Vec<_> = tree.iter().map(|node| node.path.as_str()).collect();
+        assert_eq!(paths, ["work", "work/acme"]);
+    }
+}
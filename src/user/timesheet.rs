@@ -0,0 +1,190 @@
+use super::Config;
+
+use std::time::Duration;
+
+use chrono::{DateTime, Datelike, Days, Local, NaiveDate, NaiveTime, TimeZone, Weekday};
+
+/// The time of one activity under one tag, for each day of a week.
+pub struct TimesheetRow {
+    pub name: String,
+    pub tag_index: usize,
+    pub color_index: usize,
+    pub days: [Duration; 7],
+}
+
+impl TimesheetRow {
+    pub fn total(&self) -> Duration {
+        self.days.iter().sum()
+    }
+}
+
+/// A week of tracked time, built from when each session was started.
+pub struct Timesheet {
+    /// The first day of the week.
+    pub week_start: NaiveDate,
+    pub rows: Vec<TimesheetRow>,
+}
+
+impl Timesheet {
+    /// Builds the timesheet of the week starting at `week_start`. `extra_rows` are
+    /// `(name, tag_index, color_index)` rows to show even if they have no time yet.
+    pub fn new(
+        config: &Config,
+        week_start: NaiveDate,
+        extra_rows: &[(String, usize, usize)],
+    ) -> Self {
+        let mut rows: Vec<TimesheetRow> = Vec::new();
+        for entry in &config.entry {
+            let mut days = [Duration::ZERO; 7];
+            for (day, time) in days.iter_mut().enumerate() {
+                let (from, to) = Self::day_bounds(week_start + Days::new(day as u64));
+                *time = entry.time_between(from, to);
+            }
+
+            if days.iter().all(|time| time.is_zero()) {
+                continue;
+            }
+
+            let row = rows
+                .iter_mut()
+                .find(|row| row.name == entry.name && row.tag_index == entry.tag_index);
+            match row {
+                Some(row) => {
+                    for (total, time) in row.days.iter_mut().zip(days) {
+                        *total += time;
+                    }
+                }
+                None => rows.push(TimesheetRow {
+                    name: entry.name.clone(),
+                    tag_index: entry.tag_index,
+                    color_index: entry.color_index,
+                    days,
+                }),
+            }
+        }
+
+        for (name, tag_index, color_index) in extra_rows {
+            if !rows
+                .iter()
+                .any(|row| row.name == *name && row.tag_index == *tag_index)
+            {
+                rows.push(TimesheetRow {
+                    name: name.clone(),
+                    tag_index: *tag_index,
+                    color_index: *color_index,
+                    days: [Duration::ZERO; 7],
+                });
+            }
+        }
+
+        Self { week_start, rows }
+    }
+
+    pub fn day(&self, day: usize) -> NaiveDate {
+        self.week_start + Days::new(day as u64)
+    }
+
+    /// The total of every row on `day`.
+    pub fn day_total(&self, day: usize) -> Duration {
+        self.rows.iter().map(|row| row.days[day]).sum()
+    }
+
+    pub fn total(&self) -> Duration {
+        self.rows.iter().map(|row| row.total()).sum()
+    }
+
+    /// The first day of the week `date` is in, weeks starting on `week_start`.
+    pub fn week_of(date: NaiveDate, week_start: Weekday) -> NaiveDate {
+        let days =
+            (7 + date.weekday().num_days_from_monday() - week_start.num_days_from_monday()) % 7;
+        date - Days::new(days as u64)
+    }
+
+    /// When the week starting on `week_start` begins and ends.
+    pub fn week_bounds(week_start: NaiveDate) -> (DateTime<Local>, DateTime<Local>) {
+        let (start, _) = Self::day_bounds(week_start);
+        let (end, _) = Self::day_bounds(week_start + Days::new(7));
+        (start, end)
+    }
+
+    /// When `day` begins and ends.
+    pub fn day_bounds(day: NaiveDate) -> (DateTime<Local>, DateTime<Local>) {
+        let midnight = |day: NaiveDate| {
+            Local
+                .from_local_datetime(&day.and_time(NaiveTime::MIN))
+                .earliest()
+                .unwrap_or_else(Local::now)
+        };
+
+        (midnight(day), midnight(day + Days::new(1)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn weeks_begin_on_the_week_start() {
+        let wednesday = date(2024, 5, 15);
+        assert_eq!(
+            Timesheet::week_of(wednesday, Weekday::Mon),
+            date(2024, 5, 13)
+        );
+        assert_eq!(Timesheet::week_of(wednesday, Weekday::Wed), wednesday);
+        assert_eq!(
+            Timesheet::week_of(wednesday, Weekday::Thu),
+            date(2024, 5, 9)
+        );
+        assert_eq!(
+            Timesheet::week_of(wednesday, Weekday::Sun),
+            date(2024, 5, 12)
+        );
+    }
+
+    #[test]
+    fn days_begin_at_midnight() {
+        let (start, end) = Timesheet::day_bounds(date(2024, 5, 15));
+        assert_eq!(
+            start.naive_local(),
+            date(2024, 5, 15).and_time(NaiveTime::MIN)
+        );
+        assert_eq!(
+            end.naive_local(),
+            date(2024, 5, 16).and_time(NaiveTime::MIN)
+        );
+    }
+
+    /// Days around a daylight saving change are an hour shorter or longer, but
+    /// they still follow on from each other.
+    #[test]
+    fn days_follow_on_across_daylight_saving_changes() {
+        let mut day = date(2024, 1, 1);
+        while day.year() == 2024 {
+            let (start, end) = Timesheet::day_bounds(day);
+            let (next_start, _) = Timesheet::day_bounds(day + Days::new(1));
+            assert_eq!(end, next_start);
+
+            let hours = (end - start).num_hours();
+            assert!(
+                (23..=25).contains(&hours),
+                "{} is {} hours long",
+                day,
+                hours
+            );
+
+            day = day + Days::new(1);
+        }
+    }
+
+    #[test]
+    fn weeks_span_seven_days() {
+        let (start, end) = Timesheet::week_bounds(date(2024, 3, 25));
+        assert_eq!(start, Timesheet::day_bounds(date(2024, 3, 25)).0);
+        assert_eq!(end, Timesheet::day_bounds(date(2024, 4, 1)).0);
+    }
+}